@@ -0,0 +1,1371 @@
+// Scanning for settings files, the name caches and copying with backups, kept
+// apart from the UI so they work on plain paths and in-memory data. The GUI and
+// the command line both drive these.
+
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Character data ESI returns alongside the name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CharacterDetails {
+    pub corporation_id: Option<i64>,
+    // ISO 8601 creation date, e.g. "2015-03-24T11:37:00Z"
+    pub birthday: Option<String>,
+    // Can be missing from ESI's answer, and from entries cached before it was stored
+    pub security_status: Option<f64>,
+    // Unix time (seconds) from ESI's Expires header, after which the name may be stale
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+// A resolved character name persisted between sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedName {
+    pub name: String,
+    #[serde(flatten)]
+    pub details: CharacterDetails,
+    // Unix timestamp (seconds) of when the name was resolved
+    pub cached_at: u64,
+    // Typed in by the user for a character ESI can't resolve; never looked up again
+    #[serde(default)]
+    pub user_provided: bool,
+}
+
+// How long a cached name is trusted when ESI gave no Expires time for it
+pub const UNKNOWN_EXPIRY_SECS: u64 = 24 * 60 * 60;
+
+// Whether a cached name is past the time ESI said it was good until, so scans look
+// it up again. Names without that time (cached before it was kept, or answered
+// without the header) get a day from when they were cached; typed-in names never expire.
+pub fn name_expired(entry: &CachedName, now: u64) -> bool {
+    if entry.user_provided {
+        return false;
+    }
+    let expires_at = entry.details.expires_at
+        .unwrap_or_else(|| entry.cached_at.saturating_add(UNKNOWN_EXPIRY_SECS));
+    expires_at <= now
+}
+
+// Represents a character settings file we found
+#[derive(Debug, Clone)]
+pub struct SettingsFile {
+    pub path: PathBuf,
+    pub filename: String,
+    pub character_id: String,
+    // Base path of the EVE installation the file was found under
+    pub install: PathBuf,
+    // Name of the settings_* folder the file lives in
+    pub profile: String,
+    // None when the file's metadata couldn't be read
+    pub modified: Option<SystemTime>,
+    pub size: Option<u64>,
+    // Empty or truncated, so never offered as a copy source
+    pub invalid: bool,
+    pub character_name: CharacterNameStatus,
+    pub details: CharacterDetails,
+}
+
+#[derive(Debug, Clone)]
+pub enum CharacterNameStatus {
+    Loading,
+    Found(String),
+    Error(String),
+    // Lookup was stopped before this character resolved
+    Cancelled,
+    // Offline mode is on, so no lookup was made
+    Offline,
+    // ESI answered 404, as it does for biomassed characters
+    Deleted,
+    // ESI rate-limited the lookup, which is waiting to try again
+    Retrying,
+}
+
+impl CharacterNameStatus {
+    // Still on its way, so not yet a result either way
+    pub fn is_pending(&self) -> bool {
+        matches!(self, CharacterNameStatus::Loading | CharacterNameStatus::Retrying)
+    }
+}
+
+// Where ToonSettings keeps its config and caches
+pub fn get_config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("toonsettings"))
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Read a cache file, treating a missing one as empty. One that doesn't parse (e.g.
+// cut short by a crash before writes were atomic) is moved aside to <name>.bad,
+// so the cache starts over instead of failing on every launch. Read as bytes, so a
+// file cut off in the middle of a character is caught the same way.
+fn load_cache_file<T: DeserializeOwned + Default>(path: &Path) -> T {
+    let Ok(contents) = fs::read(path) else {
+        return T::default();
+    };
+    match serde_json::from_slice(&contents) {
+        Ok(cache) => cache,
+        Err(_) => {
+            let _ = fs::rename(path, path.with_extension("json.bad"));
+            T::default()
+        }
+    }
+}
+
+// The caches' file names in `dir`, which is normally get_config_dir()
+pub const NAME_CACHE_FILE: &str = "name_cache.json";
+pub const NOT_FOUND_CACHE_FILE: &str = "not_found_cache.json";
+
+pub fn load_name_cache(dir: &Path) -> HashMap<String, CachedName> {
+    // A missing or unreadable cache just means every name gets looked up again
+    load_cache_file(&dir.join(NAME_CACHE_FILE))
+}
+
+pub fn save_name_cache(dir: &Path, cache: &HashMap<String, CachedName>) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
+
+    let contents = serde_json::to_string_pretty(cache)
+        .map_err(|e| format!("Failed to serialize name cache: {}", e))?;
+    // Replaced whole, so a crash mid-write leaves the previous cache intact
+    write_atomic(&dir.join(NAME_CACHE_FILE), contents.as_bytes())
+        .map_err(|e| format!("Failed to write name cache: {}", e))
+}
+
+// A names file maps character IDs to names, e.g. {"90000001": "Main"}. Corps can
+// share one as a roster; its names win over ESI's. Entries that aren't a numeric
+// ID with a name are skipped.
+pub fn load_names_file(path: &Path) -> Result<HashMap<String, String>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    let names: HashMap<String, String> = serde_json::from_str(&contents)
+        .map_err(|e| format!("{} isn't a JSON object of character ID to name: {}", path.display(), e))?;
+    Ok(names.into_iter()
+        .map(|(id, name)| (id.trim().to_string(), name.trim().to_string()))
+        .filter(|(id, name)| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()) && !name.is_empty())
+        .collect())
+}
+
+// Characters ESI reported as not found are left alone for this long, since a
+// deleted character stays deleted. Retrying one by hand always asks again.
+const NOT_FOUND_TTL_SECS: u64 = 30 * 24 * 60 * 60;
+
+// Character IDs that got a 404, with the Unix time (seconds) of the answer
+pub fn load_not_found_cache(dir: &Path) -> HashMap<String, u64> {
+    load_cache_file(&dir.join(NOT_FOUND_CACHE_FILE))
+}
+
+pub fn save_not_found_cache(dir: &Path, cache: &HashMap<String, u64>) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
+
+    let contents = serde_json::to_string_pretty(cache)
+        .map_err(|e| format!("Failed to serialize not-found cache: {}", e))?;
+    write_atomic(&dir.join(NOT_FOUND_CACHE_FILE), contents.as_bytes())
+        .map_err(|e| format!("Failed to write not-found cache: {}", e))
+}
+
+// The IDs whose 404 is recent enough to skip looking them up
+pub fn recent_not_found(cache: &HashMap<String, u64>) -> HashSet<String> {
+    let now = unix_now();
+    cache.iter()
+        .filter(|(_, checked_at)| now.saturating_sub(**checked_at) < NOT_FOUND_TTL_SECS)
+        .map(|(id, _)| id.clone())
+        .collect()
+}
+
+// Record a lookup result in the not-found cache; true if it changed
+pub fn update_not_found_cache(cache: &mut HashMap<String, u64>, character_id: &str, name: &CharacterNameStatus) -> bool {
+    match name {
+        CharacterNameStatus::Deleted => {
+            cache.insert(character_id.to_string(), unix_now());
+            true
+        }
+        CharacterNameStatus::Found(_) => cache.remove(character_id).is_some(),
+        _ => false,
+    }
+}
+
+// Fold one lookup answer into both caches, the same way for the GUI and the
+// command line. A name the user typed in that comes back unchanged keeps its flag,
+// and a refreshed expired name is stored again even if nothing changed, so it
+// counts as fresh. Returns whether the name cache and the not-found cache changed.
+pub fn merge_lookup_result(
+    name_cache: &mut HashMap<String, CachedName>,
+    not_found_cache: &mut HashMap<String, u64>,
+    character_id: &str,
+    name: &CharacterNameStatus,
+    details: &CharacterDetails,
+) -> (bool, bool) {
+    let not_found_dirty = update_not_found_cache(not_found_cache, character_id, name);
+    let CharacterNameStatus::Found(resolved) = name else {
+        return (false, not_found_dirty);
+    };
+    let unchanged = name_cache.get(character_id)
+        .is_some_and(|entry| entry.name == *resolved
+            && (entry.user_provided || (entry.details == *details && !name_expired(entry, unix_now()))));
+    if unchanged {
+        return (false, not_found_dirty);
+    }
+    name_cache.insert(character_id.to_string(), CachedName {
+        name: resolved.clone(),
+        details: details.clone(),
+        cached_at: unix_now(),
+        user_provided: false,
+    });
+    (true, not_found_dirty)
+}
+
+// Real character settings files are tens of kilobytes; anything smaller than
+// this was left behind by a crashed client and would wipe settings if copied
+const MIN_SETTINGS_FILE_SIZE: u64 = 512;
+
+// Order character IDs numerically; anything non-numeric sorts last, as text
+pub fn compare_character_ids(a: &str, b: &str) -> std::cmp::Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a_num), Ok(b_num)) => a_num.cmp(&b_num),
+        (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+        (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+// How many folders below the base path settings_* folders are looked for.
+// The standard layout needs two: base -> server folder -> settings_*.
+pub const DEFAULT_SCAN_DEPTH: usize = 4;
+
+pub const SCAN_DEPTH_RANGE: RangeInclusive<usize> = 2..=8;
+
+// Everything found across all configured installs
+pub struct InstallScan {
+    pub files: Vec<SettingsFile>,
+    // Installs that failed outright
+    pub errors: Vec<String>,
+    // Folders inside installs that couldn't be read
+    pub warnings: Vec<String>,
+    // Whether the main path scanned, so it's worth remembering
+    pub primary_ok: bool,
+    // Every path scanned, the main one first, as the files' `install` names them
+    pub roots: Vec<PathBuf>,
+}
+
+// Folders the walk has entered, those it couldn't read, and whether to give up
+struct ScanState<'a> {
+    visited: HashSet<PathBuf>,
+    unreadable: Vec<String>,
+    cancel: &'a AtomicBool,
+}
+
+// The settings files under a path, plus a warning for each folder that couldn't
+// be read, since any files in it are missing from the list. Setting `cancel`
+// stops the walk early.
+pub fn scan_for_settings_files(base_path: &str, max_depth: usize, cancel: &AtomicBool) -> Result<(Vec<SettingsFile>, Vec<String>), String> {
+    let path = PathBuf::from(base_path);
+    
+    if !path.exists() {
+        return Err(format!("Path does not exist: {}", base_path));
+    }
+
+    let mut files = Vec::new();
+    let char_regex = Regex::new(r"^core_char_(\d+)\.dat$").unwrap();
+    let mut state = ScanState { visited: HashSet::new(), unreadable: Vec::new(), cancel };
+
+    // Walk through the EVE directory to find settings folders
+    let settings_folders = collect_settings_files(&path, &path, 0, max_depth, &char_regex, &mut state, &mut files);
+    if cancel.load(Ordering::Relaxed) {
+        return Err("Scan cancelled".to_string());
+    }
+
+    // Tell a wrong folder apart from a right one that simply has no characters yet
+    let unreadable = if state.unreadable.is_empty() {
+        String::new()
+    } else {
+        format!(" ({} folder(s) couldn't be read: {})", state.unreadable.len(), state.unreadable.join("; "))
+    };
+    if settings_folders == 0 {
+        // A flat folder of loose files, e.g. an extracted backup, is listed as one profile
+        if let Ok(entries) = fs::read_dir(&path) {
+            let profile = path.file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| base_path.to_string());
+            push_character_files(&path, &profile, entries, &char_regex, &mut files);
+        }
+        if !files.is_empty() {
+            files.sort_by(|a, b| compare_character_ids(&a.character_id, &b.character_id));
+            return Ok((files, state.unreadable));
+        }
+        return Err(format!(
+            "No settings_* folders or core_char_*.dat files found in {} - this doesn't look like an EVE settings folder{}",
+            base_path, unreadable));
+    }
+    if files.is_empty() {
+        return Err(format!(
+            "Found {} settings_* folder(s) in {}, but no character settings files (core_char_*.dat) in them{}",
+            settings_folders, base_path, unreadable));
+    }
+
+    // Sort files by character ID
+    files.sort_by(|a, b| compare_character_ids(&a.character_id, &b.character_id));
+
+    Ok((files, state.unreadable))
+}
+
+// Recursive part of scan_for_settings_files, returning how many settings_* folders
+// it went through. Each folder is entered at most once (by canonical path), so
+// symlink loops can't keep the walk going.
+fn collect_settings_files(
+    install: &Path,
+    dir: &Path,
+    depth: usize,
+    max_depth: usize,
+    char_regex: &Regex,
+    state: &mut ScanState,
+    files: &mut Vec<SettingsFile>,
+) -> usize {
+    if state.cancel.load(Ordering::Relaxed) {
+        return 0;
+    }
+    let canonical = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    if !state.visited.insert(canonical) {
+        return 0;
+    }
+
+    let name = dir.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            state.unreadable.push(format!("{}: {}", dir.display(), e));
+            return 0;
+        }
+    };
+
+    if depth > 0 && name.starts_with("settings_") {
+        // Scan this settings folder for character files only
+        push_character_files(install, &name, entries, char_regex, files);
+        return 1;
+    }
+
+    if depth >= max_depth {
+        return 0;
+    }
+    let mut settings_folders = 0;
+    for entry in entries.flatten() {
+        let entry_path = entry.path();
+        if entry_path.is_dir() {
+            settings_folders += collect_settings_files(install, &entry_path, depth + 1, max_depth, char_regex, state, files);
+        }
+    }
+    settings_folders
+}
+
+// Add the core_char_*.dat files among `entries`, all listed under `profile`
+fn push_character_files(install: &Path, profile: &str, entries: fs::ReadDir, char_regex: &Regex, files: &mut Vec<SettingsFile>) {
+    for file_entry in entries.flatten() {
+        let file_path = file_entry.path();
+        let filename_str = file_entry.file_name().to_string_lossy().to_string();
+
+        if let Some(caps) = char_regex.captures(&filename_str) {
+            let char_id = caps[1].to_string();
+            let metadata = file_entry.metadata().ok();
+            let modified = metadata.as_ref()
+                .and_then(|m| m.modified().ok());
+            let size = metadata.as_ref().map(|m| m.len());
+            files.push(SettingsFile {
+                path: file_path,
+                filename: filename_str,
+                character_id: char_id,
+                install: install.to_path_buf(),
+                profile: profile.to_string(),
+                modified,
+                size,
+                invalid: size.map(|s| s < MIN_SETTINGS_FILE_SIZE).unwrap_or(false),
+                character_name: CharacterNameStatus::Loading,
+                details: CharacterDetails::default(),
+            });
+        }
+    }
+}
+
+// Scan the primary path (first) plus any additional installations, merging the results.
+// Fails only when no installation could be scanned at all; otherwise returns the
+// files, the errors from installs that failed, and whether the primary path worked.
+pub fn scan_installs(roots: &[String], max_depth: usize, cancel: &AtomicBool) -> Result<InstallScan, String> {
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    let mut seen = HashSet::new();
+    let mut primary_ok = false;
+
+    for (i, root) in roots.iter().enumerate() {
+        match scan_for_settings_files(root, max_depth, cancel) {
+            Ok((found, unreadable)) => {
+                primary_ok |= i == 0;
+                warnings.extend(unreadable);
+                for file in found {
+                    // The same tree listed twice (e.g. via a symlink) only shows up once
+                    let key = fs::canonicalize(&file.path).unwrap_or_else(|_| file.path.clone());
+                    if seen.insert(key) {
+                        files.push(file);
+                    }
+                }
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if errors.len() == roots.len() {
+        Err(errors.join("; "))
+    } else {
+        let roots = roots.iter().map(PathBuf::from).collect();
+        Ok(InstallScan { files, errors, warnings, primary_ok, roots })
+    }
+}
+
+// Convert days since the Unix epoch into a (year, month, day) civil date
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// Write via a temp file in the same directory and rename it into place, so the
+// destination is either fully replaced or left untouched.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let file_name = path.file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp",
+        file_name.to_string_lossy(), std::process::id()));
+
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+// Timestamp for backup file names, e.g. "20250114-180312" (UTC)
+pub fn backup_timestamp() -> String {
+    let secs = unix_now() as i64;
+    let (year, month, day) = civil_from_days(secs / 86_400);
+    let secs_of_day = secs % 86_400;
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day,
+        secs_of_day / 3600, (secs_of_day % 3600) / 60, secs_of_day % 60)
+}
+
+// Copy a file to "<name>.<timestamp>.bak" next to it before it gets overwritten.
+// A second backup of the same file within the second gets "-2", "-3", ... rather
+// than replacing the first.
+pub fn backup_file(path: &Path) -> io::Result<PathBuf> {
+    let file_name = path.file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?
+        .to_string_lossy()
+        .to_string();
+    let timestamp = backup_timestamp();
+    let mut backup_path = path.with_file_name(format!("{}.{}.bak", file_name, timestamp));
+    let mut counter = 2;
+    while backup_path.exists() {
+        backup_path = path.with_file_name(format!("{}.{}-{}.bak", file_name, timestamp, counter));
+        counter += 1;
+    }
+
+    fs::copy(path, &backup_path)
+        .map_err(|e| io::Error::new(e.kind(), format!("backup failed: {}", e)))?;
+    Ok(backup_path)
+}
+
+// Find the core_user_*.dat account file that goes with a character file.
+// File names don't link the two, so when a profile folder holds several account
+// files we pick the one written closest in time to the character file - EVE saves
+// both together when that character logs out.
+fn find_account_file(char_file: &Path) -> Option<PathBuf> {
+    let folder = char_file.parent()?;
+    let user_regex = Regex::new(r"^core_user_(\d+)\.dat$").unwrap();
+    let char_modified = fs::metadata(char_file).and_then(|m| m.modified()).ok();
+
+    let mut best: Option<(PathBuf, Duration)> = None;
+    for entry in fs::read_dir(folder).ok()?.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !user_regex.is_match(&name) {
+            continue;
+        }
+
+        let distance = match (char_modified, entry.metadata().and_then(|m| m.modified()).ok()) {
+            (Some(a), Some(b)) => a.duration_since(b).or_else(|_| b.duration_since(a)).unwrap_or_default(),
+            _ => Duration::MAX,
+        };
+        if best.as_ref().map(|(_, d)| distance < *d).unwrap_or(true) {
+            best = Some((entry.path(), distance));
+        }
+    }
+
+    best.map(|(path, _)| path)
+}
+
+// How a copy treats the files it writes
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CopyOptions {
+    // Also copy the core_user_*.dat account file that goes with the source
+    pub include_account_settings: bool,
+    // Give destinations the source's modified time rather than "now"
+    pub preserve_modified: bool,
+    // Read each written file back and compare it with what was meant to be written
+    pub verify_after_copy: bool,
+    // Leave destinations that already match the source byte for byte alone
+    pub skip_identical: bool,
+    // Make read-only destinations writable before replacing them
+    pub clear_read_only: bool,
+}
+
+// Result of copying one character's settings over others
+pub struct CopyOutcome {
+    pub success_count: usize,
+    pub errors: Vec<String>,
+    // Problems that didn't stop a file from being copied
+    pub warnings: Vec<String>,
+    // Only present when account settings were requested
+    pub account: Option<AccountCopyOutcome>,
+    // Every file (or missing destination) the copy touched, for the operation log
+    pub file_results: Vec<(String, Result<(), String>)>,
+    // Backup made of each overwritten file, and the file it was made from
+    pub backups: Vec<(PathBuf, PathBuf)>,
+    // Scripts written to put those backups back
+    pub restore_scripts: Vec<PathBuf>,
+    // Destination files left alone because they already matched the source
+    pub identical: Vec<PathBuf>,
+}
+
+pub struct AccountCopyOutcome {
+    pub source_found: bool,
+    pub success_count: usize,
+    pub errors: Vec<String>,
+}
+
+impl CopyOutcome {
+    pub fn is_success(&self) -> bool {
+        self.errors.is_empty()
+            && self.account.as_ref().map(|account| account.errors.is_empty()).unwrap_or(true)
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = if self.errors.is_empty() {
+            format!("Successfully copied settings to {} character(s)", self.success_count)
+        } else {
+            format!("Copied to {} character(s), but {} failed: {}",
+                self.success_count, self.errors.len(), self.errors.join(", "))
+        };
+        summary.push_str(&self.identical_note());
+
+        if let Some(account) = &self.account {
+            if !account.source_found {
+                summary.push_str("\nNo account settings file found for the source");
+            } else if account.errors.is_empty() {
+                summary.push_str(&format!("\nAccount settings copied for {} character(s)",
+                    account.success_count));
+            } else {
+                summary.push_str(&format!("\nAccount settings copied for {} character(s), but {} failed: {}",
+                    account.success_count, account.errors.len(), account.errors.join(", ")));
+            }
+        }
+
+        if !self.warnings.is_empty() {
+            summary.push_str(&format!("\nWarnings: {}", self.warnings.join(", ")));
+        }
+        summary.push_str(&self.restore_script_note());
+        summary
+    }
+
+    pub fn identical_note(&self) -> String {
+        if self.identical.is_empty() {
+            String::new()
+        } else {
+            format!("; {} file(s) already identical and left alone", self.identical.len())
+        }
+    }
+
+    pub fn restore_script_note(&self) -> String {
+        if self.restore_scripts.is_empty() {
+            return String::new();
+        }
+        let scripts: Vec<String> = self.restore_scripts.iter().map(|p| p.display().to_string()).collect();
+        format!("\nTo undo, run {}", scripts.join(", "))
+    }
+
+    // Overall counts only, for above a per-file table that carries the errors
+    pub fn headline(&self) -> String {
+        let mut headline = if self.errors.is_empty() {
+            format!("Successfully copied settings to {} character(s)", self.success_count)
+        } else {
+            format!("Copied to {} of {} character(s); {} failed",
+                self.success_count, self.success_count + self.errors.len(), self.errors.len())
+        };
+        headline.push_str(&self.identical_note());
+        if self.account.as_ref().is_some_and(|account| !account.source_found) {
+            headline.push_str("\nNo account settings file found for the source");
+        }
+        if !self.warnings.is_empty() {
+            headline.push_str(&format!("\nWarnings: {}", self.warnings.join(", ")));
+        }
+        headline.push_str(&self.restore_script_note());
+        headline
+    }
+}
+
+// Catch writes that were silently truncated or mangled, e.g. on a flaky network drive
+fn verify_written(path: &Path, expected: &[u8]) -> io::Result<()> {
+    let written = fs::read(path)?;
+    if written == expected {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "verification failed ({} bytes on disk, expected {})", written.len(), expected.len())))
+    }
+}
+
+// Carry the source's permission bits (and optionally its modified time) over to
+// a freshly written copy, which would otherwise get defaults and "now"
+fn copy_file_attributes(source: &Path, dest: &Path, preserve_modified: bool) -> io::Result<()> {
+    let metadata = fs::metadata(source)?;
+    // Before the permissions, which may make the file read-only
+    if preserve_modified {
+        fs::File::options().write(true).open(dest)?.set_modified(metadata.modified()?)?;
+    }
+    fs::set_permissions(dest, metadata.permissions())
+}
+
+// Identifies one file in messages; the same character can have a file in several profiles
+fn file_label(file: &SettingsFile) -> String {
+    format!("{} in {}", file.character_id, file.profile)
+}
+
+// For each character with files in several profiles, the most recently written one.
+// EVE saves into the profile it is using, so that is the one it will read next.
+pub fn active_profile_files(files: &[SettingsFile]) -> HashSet<PathBuf> {
+    let mut newest: HashMap<&str, (&SettingsFile, usize)> = HashMap::new();
+    for file in files {
+        let entry = newest.entry(file.character_id.as_str()).or_insert((file, 0));
+        entry.1 += 1;
+        if file.modified > entry.0.modified {
+            entry.0 = file;
+        }
+    }
+    newest.into_values()
+        .filter(|(file, count)| *count > 1 && file.modified.is_some())
+        .map(|(file, _)| file.path.clone())
+        .collect()
+}
+
+// Copy the source settings file over each destination, backing up every file
+// before it is replaced. Files are picked by path, so a character with files in
+// several profiles only has the chosen ones written. Shared by the GUI and the
+// command line. Fails without touching anything if the source can't be used.
+pub fn copy_settings_files(
+    files: &[SettingsFile],
+    source: &Path,
+    dest_paths: &[PathBuf],
+    options: CopyOptions,
+) -> Result<CopyOutcome, String> {
+    if dest_paths.is_empty() {
+        return Err("No destinations selected".to_string());
+    }
+
+    // Find the source file
+    let source_path = match files.iter().find(|f| f.path == source) {
+        Some(f) if f.invalid => return Err("Source file is empty or truncated".to_string()),
+        Some(f) => f.path.clone(),
+        None => return Err("Source file not found".to_string()),
+    };
+
+    // Read source file contents
+    let source_contents = fs::read(&source_path)
+        .map_err(|e| format!("Failed to read source: {}", e))?;
+
+    // Account settings of the source, if requested and present
+    let source_account = if options.include_account_settings {
+        find_account_file(&source_path)
+            .and_then(|path| fs::read(&path).ok().map(|contents| (path, contents)))
+    } else {
+        None
+    };
+
+    let mut outcome = CopyOutcome {
+        success_count: 0,
+        errors: Vec::new(),
+        warnings: Vec::new(),
+        account: options.include_account_settings.then(|| AccountCopyOutcome {
+            source_found: source_account.is_some(),
+            success_count: 0,
+            errors: Vec::new(),
+        }),
+        file_results: Vec::new(),
+        backups: Vec::new(),
+        restore_scripts: Vec::new(),
+        identical: Vec::new(),
+    };
+    let already_identical = |path: &Path, contents: &[u8]| {
+        options.skip_identical && fs::read(path).is_ok_and(|current| current == contents)
+    };
+    // Destinations in one profile folder usually share an account file, and a path
+    // may be listed twice; each file is backed up and written only once, so its
+    // backup (and the restore script) always holds what was there before the copy
+    let mut handled: HashSet<PathBuf> = HashSet::new();
+
+    // Copy to each destination
+    for dest_path in dest_paths {
+        let dest = match files.iter().find(|f| f.path == *dest_path) {
+            Some(dest) => dest,
+            None => {
+                outcome.errors.push(format!("{}: settings file not found", dest_path.display()));
+                outcome.file_results.push((dest_path.display().to_string(), Err("settings file not found".to_string())));
+                continue;
+            }
+        };
+        if !handled.insert(dest.path.clone()) {
+            continue;
+        }
+        let dest_id = file_label(dest);
+
+        if already_identical(&dest.path, &source_contents) {
+            outcome.identical.push(dest.path.clone());
+        } else {
+            let backup = unlock_for_copy(&dest.path, options).and_then(|_| backup_file(&dest.path));
+            if let Ok(backup_path) = &backup {
+                outcome.backups.push((backup_path.clone(), dest.path.clone()));
+            }
+            let written = backup
+                .and_then(|_| write_atomic(&dest.path, &source_contents))
+                .and_then(|_| if options.verify_after_copy {
+                    verify_written(&dest.path, &source_contents)
+                } else {
+                    Ok(())
+                });
+            outcome.file_results.push((dest.path.display().to_string(),
+                written.as_ref().map(|_| ()).map_err(|e| e.to_string())));
+            match written {
+                Ok(_) => {
+                    outcome.success_count += 1;
+                    if let Err(e) = copy_file_attributes(&source_path, &dest.path, options.preserve_modified) {
+                        outcome.warnings.push(format!("{}: couldn't copy file attributes: {}", dest_id, e));
+                    }
+                }
+                Err(e) => outcome.errors.push(format!("{}: {}", dest_id, e)),
+            }
+        }
+
+        // Destinations without an account file of their own are skipped silently
+        if let (Some((source_account_path, account_contents)), Some(account)) =
+            (&source_account, outcome.account.as_mut())
+        {
+            // A profile's shared account file is the source's own and is never written
+            let dest_account = find_account_file(&dest.path)
+                .filter(|path| path != source_account_path && handled.insert(path.clone()));
+            if let Some(dest_account_path) = dest_account {
+                if already_identical(&dest_account_path, account_contents) {
+                    outcome.identical.push(dest_account_path);
+                } else {
+                    let backup = unlock_for_copy(&dest_account_path, options)
+                        .and_then(|_| backup_file(&dest_account_path));
+                    if let Ok(backup_path) = &backup {
+                        outcome.backups.push((backup_path.clone(), dest_account_path.clone()));
+                    }
+                    let written = backup
+                        .and_then(|_| write_atomic(&dest_account_path, account_contents))
+                        .and_then(|_| if options.verify_after_copy {
+                            verify_written(&dest_account_path, account_contents)
+                        } else {
+                            Ok(())
+                        });
+                    outcome.file_results.push((dest_account_path.display().to_string(),
+                        written.as_ref().map(|_| ()).map_err(|e| e.to_string())));
+                    match written {
+                        Ok(_) => {
+                            account.success_count += 1;
+                            let attributes = copy_file_attributes(
+                                source_account_path, &dest_account_path, options.preserve_modified);
+                            if let Err(e) = attributes {
+                                outcome.warnings.push(format!(
+                                    "{} account file: couldn't copy file attributes: {}", dest_id, e));
+                            }
+                        }
+                        Err(e) => account.errors.push(format!("{}: {}", dest_id, e)),
+                    }
+                }
+            }
+        }
+    }
+
+    for script in write_restore_scripts(&outcome.backups) {
+        match script {
+            Ok(path) => outcome.restore_scripts.push(path),
+            Err(e) => outcome.warnings.push(e),
+        }
+    }
+
+    Ok(outcome)
+}
+
+// Exchange the settings of two character files, through the same backups,
+// read-only handling, verification and restore scripts as a copy. Only the
+// character files are swapped; account settings stay where they are. If the
+// second write fails the first file is put back, so both never end up alike.
+pub fn swap_settings_files(
+    files: &[SettingsFile],
+    first: &Path,
+    second: &Path,
+    options: CopyOptions,
+) -> Result<CopyOutcome, String> {
+    let usable = |path: &Path| match files.iter().find(|f| f.path == path) {
+        Some(f) if f.invalid => Err(format!("{} is empty or truncated", file_label(f))),
+        Some(f) => Ok(f),
+        None => Err(format!("{}: settings file not found", path.display())),
+    };
+    let (first, second) = (usable(first)?, usable(second)?);
+    if first.path == second.path {
+        return Err("Can't swap a file with itself".to_string());
+    }
+
+    let first_contents = fs::read(&first.path).map_err(|e| format!("Failed to read {}: {}", file_label(first), e))?;
+    let second_contents = fs::read(&second.path).map_err(|e| format!("Failed to read {}: {}", file_label(second), e))?;
+
+    let mut outcome = CopyOutcome {
+        success_count: 0,
+        errors: Vec::new(),
+        warnings: Vec::new(),
+        account: None,
+        file_results: Vec::new(),
+        backups: Vec::new(),
+        restore_scripts: Vec::new(),
+        identical: Vec::new(),
+    };
+    if options.skip_identical && first_contents == second_contents {
+        outcome.identical = vec![first.path.clone(), second.path.clone()];
+        return Ok(outcome);
+    }
+
+    let write = |file: &SettingsFile, contents: &[u8], outcome: &mut CopyOutcome| {
+        let backup = unlock_for_copy(&file.path, options).and_then(|_| backup_file(&file.path));
+        if let Ok(backup_path) = &backup {
+            outcome.backups.push((backup_path.clone(), file.path.clone()));
+        }
+        backup
+            .and_then(|_| write_atomic(&file.path, contents))
+            .and_then(|_| if options.verify_after_copy {
+                verify_written(&file.path, contents)
+            } else {
+                Ok(())
+            })
+    };
+
+    let first_written = write(first, &second_contents, &mut outcome);
+    let second_written = match &first_written {
+        Ok(_) => {
+            let written = write(second, &first_contents, &mut outcome);
+            if written.is_err() {
+                // Put the first file back, since the second never got its settings
+                if let Err(e) = write_atomic(&first.path, &first_contents) {
+                    outcome.warnings.push(format!("{}: couldn't put back its settings: {}", file_label(first), e));
+                }
+            }
+            written
+        }
+        Err(_) => Err(io::Error::other("not swapped, the other file failed")),
+    };
+    let swapped = first_written.is_ok() && second_written.is_ok();
+    for (file, written) in [(first, first_written), (second, second_written)] {
+        // A rolled-back first file didn't keep the swap either
+        let written = match written {
+            Ok(_) if !swapped => Err(io::Error::other("put back, the other file failed")),
+            written => written,
+        };
+        outcome.file_results.push((file.path.display().to_string(),
+            written.as_ref().map(|_| ()).map_err(|e| e.to_string())));
+        match written {
+            Ok(_) => outcome.success_count += 1,
+            Err(e) => outcome.errors.push(format!("{}: {}", file_label(file), e)),
+        }
+    }
+
+    for script in write_restore_scripts(&outcome.backups) {
+        match script {
+            Ok(path) => outcome.restore_scripts.push(path),
+            Err(e) => outcome.warnings.push(e),
+        }
+    }
+
+    Ok(outcome)
+}
+
+fn is_read_only(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.permissions().readonly())
+}
+
+// The files a copy to these destinations would replace that are marked read-only,
+// which can't always be replaced (never on Windows)
+pub fn read_only_destinations(files: &[SettingsFile], source: &Path, dest_paths: &[PathBuf], options: CopyOptions) -> Vec<PathBuf> {
+    let source_account = options.include_account_settings
+        .then(|| find_account_file(source))
+        .flatten();
+    let mut read_only = Vec::new();
+    for dest_path in dest_paths {
+        if !files.iter().any(|f| f.path == *dest_path) {
+            continue;
+        }
+        if is_read_only(dest_path) {
+            read_only.push(dest_path.clone());
+        }
+        let dest_account = source_account.as_ref()
+            .and_then(|source_account| find_account_file(dest_path).filter(|path| path != source_account));
+        if let Some(account_path) = dest_account.filter(|path| is_read_only(path) && !read_only.contains(path)) {
+            read_only.push(account_path);
+        }
+    }
+    read_only
+}
+
+// Clear the read-only flag on a file about to be replaced, if the copy asked for it
+fn unlock_for_copy(path: &Path, options: CopyOptions) -> io::Result<()> {
+    if options.clear_read_only && is_read_only(path) {
+        make_writable(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("couldn't clear read-only flag: {}", e)))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn make_writable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = fs::metadata(path)?.permissions().mode();
+    fs::set_permissions(path, fs::Permissions::from_mode(mode | 0o200))
+}
+
+#[cfg(not(unix))]
+#[allow(clippy::permissions_set_readonly_false)]
+fn make_writable(path: &Path) -> io::Result<()> {
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_readonly(false);
+    fs::set_permissions(path, permissions)
+}
+
+// An undo for a copy that works without the app: one script in each folder that
+// got backups, copying every backup there back over the file it was made from.
+// Backups sit next to their files, so the scripts only need bare file names.
+fn write_restore_scripts(backups: &[(PathBuf, PathBuf)]) -> Vec<Result<PathBuf, String>> {
+    let mut folders: Vec<&Path> = backups.iter().filter_map(|(backup, _)| backup.parent()).collect();
+    folders.sort();
+    folders.dedup();
+
+    let windows = cfg!(target_os = "windows");
+    let timestamp = backup_timestamp();
+    folders.into_iter().map(|folder| {
+        let restores: Vec<(String, String)> = backups.iter()
+            .filter(|(backup, _)| backup.parent() == Some(folder))
+            .filter_map(|(backup, original)| Some((
+                backup.file_name()?.to_string_lossy().to_string(),
+                original.file_name()?.to_string_lossy().to_string(),
+            )))
+            .collect();
+
+        let mut script = String::new();
+        let extension = if windows {
+            script.push_str(&format!("# Puts back the settings files ToonSettings backed up at {} (UTC).\r\n", timestamp));
+            script.push_str("# Close EVE before running this.\r\n");
+            script.push_str("Set-Location -LiteralPath $PSScriptRoot\r\n");
+            for (backup, original) in &restores {
+                script.push_str(&format!("Copy-Item -LiteralPath '{}' -Destination '{}' -Force\r\n",
+                    backup.replace('\'', "''"), original.replace('\'', "''")));
+            }
+            script.push_str(&format!("Write-Host 'Restored {} file(s)'\r\n", restores.len()));
+            "ps1"
+        } else {
+            script.push_str("#!/bin/sh\n");
+            script.push_str(&format!("# Puts back the settings files ToonSettings backed up at {} (UTC).\n", timestamp));
+            script.push_str("# Close EVE before running this.\n");
+            script.push_str("cd \"$(dirname \"$0\")\" || exit 1\n");
+            for (backup, original) in &restores {
+                script.push_str(&format!("cp -f '{}' '{}' || exit 1\n",
+                    backup.replace('\'', "'\\''"), original.replace('\'', "'\\''")));
+            }
+            script.push_str(&format!("echo 'Restored {} file(s)'\n", restores.len()));
+            "sh"
+        };
+
+        create_restore_script(folder, &timestamp, extension, &script)
+            .map_err(|e| format!("couldn't write restore script in {}: {}", folder.display(), e))
+    }).collect()
+}
+
+// Write a new "restore_<timestamp>.<extension>" script, never replacing one: a
+// second copy into the folder within the second gets "-2", "-3", ... as backups do
+fn create_restore_script(folder: &Path, timestamp: &str, extension: &str, script: &str) -> io::Result<PathBuf> {
+    let mut path = folder.join(format!("restore_{}.{}", timestamp, extension));
+    let mut counter = 2;
+    let mut file = loop {
+        match fs::File::options().write(true).create_new(true).open(&path) {
+            Ok(file) => break file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                path = folder.join(format!("restore_{}-{}.{}", timestamp, counter, extension));
+                counter += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    };
+    file.write_all(script.as_bytes())?;
+    make_executable(&path)?;
+    Ok(path)
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+// Seed a settings file for a character that hasn't logged in yet, from the source's
+// file. Never overwrites: an existing file should be picked as a normal destination.
+pub fn create_settings_file(
+    files: &[SettingsFile],
+    source: &Path,
+    new_id: &str,
+    folder: &Path,
+    options: CopyOptions,
+) -> Result<PathBuf, String> {
+    if new_id.is_empty() || !new_id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("\"{}\" isn't a character ID; IDs are numbers only", new_id));
+    }
+
+    let source_path = match files.iter().find(|f| f.path == source) {
+        Some(f) if f.invalid => return Err("Source file is empty or truncated".to_string()),
+        Some(f) => f.path.clone(),
+        None => return Err("Source file not found".to_string()),
+    };
+
+    let path = folder.join(format!("core_char_{}.dat", new_id));
+    if path.exists() {
+        return Err(format!("{} already exists; select it under Copy To instead", path.display()));
+    }
+
+    let contents = fs::read(&source_path).map_err(|e| format!("Failed to read source: {}", e))?;
+    write_atomic(&path, &contents)
+        .and_then(|_| if options.verify_after_copy { verify_written(&path, &contents) } else { Ok(()) })
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    // The file exists now, so a failure here isn't worth reporting as one
+    let _ = copy_file_attributes(&source_path, &path, options.preserve_modified);
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An empty folder of its own for each test, as tests run in parallel
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("toonsettings_core_test_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn scan(dir: &Path) -> Result<(Vec<SettingsFile>, Vec<String>), String> {
+        scan_for_settings_files(&dir.to_string_lossy(), DEFAULT_SCAN_DEPTH, &AtomicBool::new(false))
+    }
+
+    #[test]
+    fn scan_without_settings_folders_says_so() {
+        let dir = test_dir("no_settings_folders");
+        fs::create_dir_all(dir.join("logs")).unwrap();
+        fs::write(dir.join("readme.txt"), "not a settings file").unwrap();
+
+        let error = scan(&dir).unwrap_err();
+        assert!(error.starts_with("No settings_* folders or core_char_*.dat files found in"), "{}", error);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn scan_of_settings_folder_without_characters_says_so() {
+        let dir = test_dir("no_characters");
+        let profile = dir.join("c_tq/settings_Default");
+        fs::create_dir_all(&profile).unwrap();
+        fs::write(profile.join("core_user_1.dat"), [0u8; 600]).unwrap();
+
+        let error = scan(&dir).unwrap_err();
+        assert!(error.starts_with("Found 1 settings_* folder(s) in"), "{}", error);
+        assert!(error.contains("but no character settings files (core_char_*.dat) in them"), "{}", error);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn scan_lists_loose_character_files() {
+        let dir = test_dir("loose_files");
+        fs::write(dir.join("core_char_222.dat"), [0u8; 600]).unwrap();
+        fs::write(dir.join("core_char_111.dat"), [0u8; 10]).unwrap();
+        fs::write(dir.join("core_user_1.dat"), [0u8; 600]).unwrap();
+
+        let (files, warnings) = scan(&dir).unwrap();
+        assert!(warnings.is_empty());
+        let ids: Vec<&str> = files.iter().map(|f| f.character_id.as_str()).collect();
+        assert_eq!(ids, ["111", "222"]);
+        let folder = dir.file_name().unwrap().to_string_lossy().to_string();
+        assert!(files.iter().all(|f| f.profile == folder && f.install == dir));
+        assert!(files[0].invalid && !files[1].invalid);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn caches_round_trip_through_a_folder() {
+        let dir = test_dir("caches");
+        let names = HashMap::from([("111".to_string(), CachedName {
+            name: "Pilot".to_string(),
+            details: CharacterDetails { corporation_id: Some(98000001), ..Default::default() },
+            cached_at: 5,
+            user_provided: false,
+        })]);
+        save_name_cache(&dir, &names).unwrap();
+        let loaded = load_name_cache(&dir);
+        assert_eq!(loaded["111"].name, "Pilot");
+        assert_eq!(loaded["111"].details, names["111"].details);
+
+        let not_found = HashMap::from([("222".to_string(), 7)]);
+        save_not_found_cache(&dir, &not_found).unwrap();
+        assert_eq!(load_not_found_cache(&dir), not_found);
+
+        // Nothing saved yet in a folder means empty caches, not errors
+        let empty = test_dir("caches_empty");
+        assert!(load_name_cache(&empty).is_empty());
+        assert!(load_not_found_cache(&empty).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+        let _ = fs::remove_dir_all(&empty);
+    }
+
+    #[test]
+    fn cut_off_cache_is_set_aside() {
+        let dir = test_dir("cut_off_cache");
+        let path = dir.join("name_cache.json");
+        let bad = dir.join("name_cache.json.bad");
+
+        fs::write(&path, r#"{"111": {"name": "Pil"#).unwrap();
+        assert!(load_name_cache(&dir).is_empty());
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(&bad).unwrap(), r#"{"111": {"name": "Pil"#);
+
+        // Cut off inside a multi-byte character, so not even valid UTF-8
+        let mut cut = r#"{"111": {"name": "Zoë"#.as_bytes().to_vec();
+        cut.pop();
+        fs::write(&path, &cut).unwrap();
+        assert!(load_name_cache(&dir).is_empty());
+        assert!(!path.exists());
+        assert_eq!(fs::read(&bad).unwrap(), cut);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn write_atomic_replaces_and_leaves_no_temp_file() {
+        let dir = test_dir("write_atomic");
+        let path = dir.join("config.json");
+        fs::write(&path, "old contents that are longer").unwrap();
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        let entries: Vec<_> = fs::read_dir(&dir).unwrap().flatten().map(|e| e.file_name()).collect();
+        assert_eq!(entries, ["config.json"]);
+        assert!(write_atomic(&dir.join("missing/config.json"), b"x").is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn backup_file_keeps_the_original_contents() {
+        let dir = test_dir("backup_file");
+        let path = dir.join("core_char_111.dat");
+        fs::write(&path, "original").unwrap();
+        let backup = backup_file(&path).unwrap();
+        fs::write(&path, "replaced").unwrap();
+
+        assert_eq!(backup.parent(), Some(dir.as_path()));
+        let name = backup.file_name().unwrap().to_string_lossy().to_string();
+        assert!(name.starts_with("core_char_111.dat.") && name.ends_with(".bak"), "{}", name);
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "original");
+        assert!(backup_file(&dir.join("missing.dat")).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn backups_of_one_file_in_the_same_second_get_their_own_names() {
+        let dir = test_dir("backup_names");
+        let path = dir.join("core_user_1.dat");
+        fs::write(&path, "first").unwrap();
+        let first = backup_file(&path).unwrap();
+        fs::write(&path, "second").unwrap();
+        let second = backup_file(&path).unwrap();
+        // Could straddle a second boundary, in which case the names differ anyway
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "first");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "second");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn shared_account_file_is_written_and_restored_once() {
+        let dir = test_dir("shared_account");
+        let source_profile = dir.join("c_tq/settings_Main");
+        let dest_profile = dir.join("c_tq/settings_Alts");
+        fs::create_dir_all(&source_profile).unwrap();
+        fs::create_dir_all(&dest_profile).unwrap();
+        fs::write(source_profile.join("core_char_111.dat"), [1u8; 600]).unwrap();
+        fs::write(source_profile.join("core_user_1.dat"), [7u8; 600]).unwrap();
+        fs::write(dest_profile.join("core_char_222.dat"), [2u8; 600]).unwrap();
+        fs::write(dest_profile.join("core_char_333.dat"), [3u8; 600]).unwrap();
+        fs::write(dest_profile.join("core_user_2.dat"), [8u8; 600]).unwrap();
+        let (files, _) = scan(&dir).unwrap();
+
+        let dests = [dest_profile.join("core_char_222.dat"), dest_profile.join("core_char_333.dat"), dest_profile.join("core_char_333.dat")];
+        let options = CopyOptions { include_account_settings: true, ..Default::default() };
+        let outcome = copy_settings_files(&files, &source_profile.join("core_char_111.dat"), &dests, options).unwrap();
+        assert!(outcome.is_success());
+        assert_eq!(outcome.account.as_ref().map(|a| a.success_count), Some(1));
+
+        let account = dest_profile.join("core_user_2.dat");
+        assert_eq!(fs::read(&account).unwrap(), [7u8; 600]);
+        let originals: Vec<&PathBuf> = outcome.backups.iter().map(|(_, original)| original).collect();
+        assert_eq!(originals.len(), 3);
+        assert_eq!(originals.iter().filter(|path| ***path == account).count(), 1);
+        let (account_backup, _) = outcome.backups.iter().find(|(_, original)| *original == account).unwrap();
+        assert_eq!(fs::read(account_backup).unwrap(), [8u8; 600]);
+
+        let script = fs::read_to_string(&outcome.restore_scripts[0]).unwrap();
+        assert_eq!(script.matches("core_user_2.dat'").count(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn copy_overwrites_destinations_and_backs_them_up() {
+        let dir = test_dir("copy");
+        let profile = dir.join("c_tq/settings_Default");
+        fs::create_dir_all(&profile).unwrap();
+        fs::write(profile.join("core_char_111.dat"), [1u8; 600]).unwrap();
+        fs::write(profile.join("core_char_222.dat"), [2u8; 700]).unwrap();
+        fs::write(profile.join("core_char_333.dat"), [3u8; 800]).unwrap();
+        let (files, _) = scan(&dir).unwrap();
+        let path = |id: &str| profile.join(format!("core_char_{}.dat", id));
+
+        let outcome = copy_settings_files(&files, &path("111"), &[path("222")], CopyOptions::default()).unwrap();
+        assert!(outcome.is_success());
+        assert_eq!(outcome.success_count, 1);
+        assert_eq!(fs::read(path("222")).unwrap(), [1u8; 600]);
+        assert_eq!(fs::read(path("333")).unwrap(), [3u8; 800]);
+        assert_eq!(outcome.backups.len(), 1);
+        assert_eq!(outcome.backups[0].1, path("222"));
+        assert_eq!(fs::read(&outcome.backups[0].0).unwrap(), [2u8; 700]);
+        assert_eq!(outcome.restore_scripts.len(), 1);
+
+        // Already matching destinations are left alone when asked
+        let options = CopyOptions { skip_identical: true, ..Default::default() };
+        let outcome = copy_settings_files(&files, &path("111"), &[path("222")], options).unwrap();
+        assert_eq!(outcome.identical, [path("222")]);
+        assert!(outcome.backups.is_empty());
+
+        assert!(copy_settings_files(&files, &path("999"), &[path("222")], CopyOptions::default()).is_err());
+        assert!(copy_settings_files(&files, &path("111"), &[], CopyOptions::default()).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn swap_exchanges_files_and_refuses_truncated_ones() {
+        let dir = test_dir("swap");
+        let profile = dir.join("c_tq/settings_Default");
+        fs::create_dir_all(&profile).unwrap();
+        fs::write(profile.join("core_char_111.dat"), [1u8; 600]).unwrap();
+        fs::write(profile.join("core_char_222.dat"), [2u8; 700]).unwrap();
+        fs::write(profile.join("core_char_333.dat"), [3u8; 10]).unwrap();
+        let (files, _) = scan(&dir).unwrap();
+        let path = |id: &str| profile.join(format!("core_char_{}.dat", id));
+
+        let outcome = swap_settings_files(&files, &path("111"), &path("222"), CopyOptions::default()).unwrap();
+        assert!(outcome.is_success());
+        assert_eq!(outcome.success_count, 2);
+        assert_eq!(fs::read(path("111")).unwrap(), [2u8; 700]);
+        assert_eq!(fs::read(path("222")).unwrap(), [1u8; 600]);
+        assert_eq!(outcome.backups.len(), 2);
+        assert_eq!(outcome.restore_scripts.len(), 1);
+
+        // A truncated side would wipe out the other's good settings
+        let error = swap_settings_files(&files, &path("111"), &path("333"), CopyOptions::default()).err().unwrap();
+        assert!(error.contains("empty or truncated"), "{}", error);
+        assert_eq!(fs::read(path("111")).unwrap(), [2u8; 700]);
+        assert_eq!(fs::read(path("333")).unwrap(), [3u8; 10]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn restore_scripts_in_the_same_second_get_their_own_names() {
+        let dir = test_dir("restore_names");
+        let profile = dir.join("c_tq/settings_Default");
+        fs::create_dir_all(&profile).unwrap();
+        fs::write(profile.join("core_char_111.dat"), [1u8; 600]).unwrap();
+        fs::write(profile.join("core_char_222.dat"), [2u8; 600]).unwrap();
+        fs::write(profile.join("core_char_333.dat"), [3u8; 600]).unwrap();
+        let (files, _) = scan(&dir).unwrap();
+        let path = |id: &str| profile.join(format!("core_char_{}.dat", id));
+
+        let first = copy_settings_files(&files, &path("111"), &[path("222")], CopyOptions::default()).unwrap();
+        let second = copy_settings_files(&files, &path("111"), &[path("333")], CopyOptions::default()).unwrap();
+        // Could straddle a second boundary, in which case the names differ anyway
+        assert_ne!(first.restore_scripts, second.restore_scripts);
+        assert!(fs::read_to_string(&first.restore_scripts[0]).unwrap().contains("core_char_222.dat'"));
+        assert!(fs::read_to_string(&second.restore_scripts[0]).unwrap().contains("core_char_333.dat'"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cached_names_expire_when_esi_says_or_after_a_day() {
+        let entry = |expires_at: Option<u64>, user_provided: bool| CachedName {
+            name: "Pilot".to_string(),
+            details: CharacterDetails { expires_at, ..Default::default() },
+            cached_at: 1000,
+            user_provided,
+        };
+        assert!(!name_expired(&entry(Some(2000), false), 1999));
+        assert!(name_expired(&entry(Some(2000), false), 2000));
+        // No Expires time: trusted for a day from when it was cached, not forever or never
+        assert!(!name_expired(&entry(None, false), 1000 + UNKNOWN_EXPIRY_SECS - 1));
+        assert!(name_expired(&entry(None, false), 1000 + UNKNOWN_EXPIRY_SECS));
+        assert!(!name_expired(&entry(Some(0), true), 5000));
+
+        // Looked up again with nothing changed, it is fresh from now on
+        let mut names = HashMap::from([("111".to_string(), entry(None, false))]);
+        let found = CharacterNameStatus::Found("Pilot".to_string());
+        let (changed, _) = merge_lookup_result(&mut names, &mut HashMap::new(), "111", &found, &CharacterDetails::default());
+        assert!(changed);
+        assert!(!name_expired(&names["111"], unix_now()));
+        let (changed, _) = merge_lookup_result(&mut names, &mut HashMap::new(), "111", &found, &CharacterDetails::default());
+        assert!(!changed);
+    }
+}
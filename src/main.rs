@@ -1,12 +1,35 @@
+mod core;
+
 use eframe::egui;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
 use regex::Regex;
-use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::ops::RangeInclusive;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::core::{
+    active_profile_files, backup_file, backup_timestamp, civil_from_days, compare_character_ids,
+    copy_settings_files, create_settings_file, get_config_dir, load_names_file, merge_lookup_result,
+    name_expired, read_only_destinations, recent_not_found, scan_for_settings_files, scan_installs,
+    swap_settings_files, unix_now, write_atomic, CachedName, CharacterDetails, CharacterNameStatus,
+    CopyOptions, CopyOutcome, InstallScan, SettingsFile, DEFAULT_SCAN_DEPTH, NAME_CACHE_FILE,
+    NOT_FOUND_CACHE_FILE, SCAN_DEPTH_RANGE,
+};
 
 // API response structure from ESI (Eve Swagger Interface)
 #[derive(Debug, Deserialize, Clone)]
@@ -15,22 +38,337 @@ struct EsiCharacterResponse {
     corporation_id: i64,
     #[serde(default)]
     birthday: Option<String>,
+    #[serde(default)]
+    security_status: Option<f64>,
+}
+
+// One entry of the bulk POST /universe/names/ response
+#[derive(Debug, Deserialize, Clone)]
+struct EsiNameResponse {
+    id: i64,
+    name: String,
+    category: String,
+}
+
+// One entry of the bulk POST /characters/affiliation/ response
+#[derive(Debug, Deserialize, Clone)]
+struct EsiAffiliationResponse {
+    character_id: i64,
+    corporation_id: i64,
+}
+
+// API response structure for the game server status
+#[derive(Debug, Deserialize, Clone)]
+struct EsiStatusResponse {
+    players: u64,
+}
+
+// API response structure for a corporation lookup
+#[derive(Debug, Deserialize, Clone)]
+struct EsiCorporationResponse {
+    name: String,
+    // Absent for NPC corporations and corporations outside an alliance
+    #[serde(default)]
+    alliance_id: Option<i64>,
+}
+
+// API response structure for an alliance lookup
+#[derive(Debug, Deserialize, Clone)]
+struct EsiAllianceResponse {
+    name: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+// How tightly the character list is packed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum Density {
+    #[default]
+    Comfortable,
+    Compact,
+}
+
+impl Density {
+    // Portrait edge length, which sets how tall a row is
+    fn portrait_size(self) -> f32 {
+        match self {
+            Density::Comfortable => 24.0,
+            Density::Compact => 18.0,
+        }
+    }
+
+    // Gap left under each row
+    fn row_gap(self) -> f32 {
+        match self {
+            Density::Comfortable => 4.0,
+            Density::Compact => 0.0,
+        }
+    }
+}
+
+// How Last Modified times are shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum TimeDisplay {
+    #[default]
+    Absolute,
+    // "3 days ago"
+    Relative,
+}
+
+impl TimeDisplay {
+    fn format(self, time: Option<SystemTime>) -> String {
+        match self {
+            TimeDisplay::Absolute => format_timestamp(time),
+            TimeDisplay::Relative => format_relative_time(time),
+        }
+    }
+
+    // The other mode, shown on hover
+    fn other(self) -> Self {
+        match self {
+            TimeDisplay::Absolute => TimeDisplay::Relative,
+            TimeDisplay::Relative => TimeDisplay::Absolute,
+        }
+    }
+}
+
+// Which EVE server ESI lookups are made against. Character IDs differ between them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum Datasource {
+    #[default]
+    Tranquility,
+    Singularity,
+}
+
+impl Datasource {
+    const ALL: [Datasource; 2] = [Datasource::Tranquility, Datasource::Singularity];
+
+    fn label(self) -> &'static str {
+        match self {
+            Datasource::Tranquility => "Tranquility",
+            Datasource::Singularity => "Singularity (test)",
+        }
+    }
+
+    // Value of ESI's `datasource` query parameter
+    fn query_value(self) -> &'static str {
+        match self {
+            Datasource::Tranquility => "tranquility",
+            Datasource::Singularity => "singularity",
+        }
+    }
+}
+
+// Where ESI requests go unless the user points them at a mirror or proxy
+const DEFAULT_ESI_BASE_URL: &str = "https://esi.evetech.net";
+
+// The base URL with any trailing slash removed, if it is an absolute http(s) URL
+fn normalize_esi_base_url(url: &str) -> Option<String> {
+    let trimmed = url.trim().trim_end_matches('/');
+    let parsed = reqwest::Url::parse(trimmed).ok()?;
+    let web = matches!(parsed.scheme(), "http" | "https");
+    (web && parsed.has_host() && parsed.query().is_none()).then(|| trimmed.to_string())
 }
 
-// Represents a character settings file we found
+// Server and host that ESI URLs are built from
 #[derive(Debug, Clone)]
-struct SettingsFile {
-    path: PathBuf,
-    filename: String,
-    character_id: String,
-    character_name: CharacterNameStatus,
+struct EsiEndpoint {
+    base_url: Arc<str>,
+    datasource: Datasource,
+}
+
+impl EsiEndpoint {
+    // e.g. url("characters/123/") -> https://esi.evetech.net/latest/characters/123/?datasource=tranquility
+    fn url(&self, path: &str) -> String {
+        format!("{}/latest/{}?datasource={}", self.base_url, path, self.datasource.query_value())
+    }
+}
+
+// A saved source + destinations selection, reapplied by character ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CopyProfile {
+    name: String,
+    source: Option<String>,
+    destinations: Vec<String>,
+}
+
+// User preferences persisted between sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct AppConfig {
+    eve_path: Option<String>,
+    // Further EVE settings trees scanned alongside eve_path
+    additional_paths: Vec<String>,
+    theme: Theme,
+    density: Density,
+    time_display: TimeDisplay,
+    datasource: Datasource,
+    // Blank means DEFAULT_ESI_BASE_URL
+    esi_base_url: String,
+    // JSON file of character ID to name that takes the place of ESI; blank for none
+    names_file: String,
+    max_concurrent_requests: usize,
+    request_timeout_secs: u64,
+    request_delay_ms: u64,
+    scan_depth: usize,
+    // Skip every ESI request; names show as "(offline)"
+    offline: bool,
+    // Keep Copy Settings disabled until the selected characters' names are in
+    wait_for_names: bool,
+    // Rescan by itself when character files change on disk
+    auto_rescan: bool,
+    // Character IDs listed above everything else, whatever the sort
+    pinned: HashSet<String>,
+    copy_profiles: Vec<CopyProfile>,
+    window_size: Option<[f32; 2]>,
+    // Not reported on every platform (e.g. Wayland)
+    window_position: Option<[f32; 2]>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            eve_path: None,
+            additional_paths: Vec::new(),
+            theme: Theme::default(),
+            density: Density::default(),
+            time_display: TimeDisplay::default(),
+            datasource: Datasource::default(),
+            esi_base_url: String::new(),
+            names_file: String::new(),
+            max_concurrent_requests: DEFAULT_CONCURRENT_REQUESTS,
+            request_timeout_secs: DEFAULT_REQUEST_TIMEOUT_SECS,
+            request_delay_ms: DEFAULT_REQUEST_DELAY_MS,
+            scan_depth: DEFAULT_SCAN_DEPTH,
+            offline: false,
+            wait_for_names: false,
+            auto_rescan: false,
+            pinned: HashSet::new(),
+            copy_profiles: Vec::new(),
+            window_size: None,
+            window_position: None,
+        }
+    }
+}
+
+impl AppConfig {
+    fn scan_depth(&self) -> usize {
+        self.scan_depth.clamp(*SCAN_DEPTH_RANGE.start(), *SCAN_DEPTH_RANGE.end())
+    }
+
+    // Falls back to the official host when the setting is blank or malformed
+    fn esi_base_url(&self) -> String {
+        normalize_esi_base_url(&self.esi_base_url).unwrap_or_else(|| DEFAULT_ESI_BASE_URL.to_string())
+    }
+
+    fn lookup_settings(&self) -> LookupSettings {
+        LookupSettings {
+            esi: EsiEndpoint {
+                base_url: self.esi_base_url().into(),
+                datasource: self.datasource,
+            },
+            // Hand-edited configs could hold anything
+            max_concurrent_requests: self.max_concurrent_requests
+                .clamp(*CONCURRENT_REQUESTS_RANGE.start(), *CONCURRENT_REQUESTS_RANGE.end()),
+            request_timeout: Duration::from_secs(self.request_timeout_secs
+                .clamp(*REQUEST_TIMEOUT_RANGE.start(), *REQUEST_TIMEOUT_RANGE.end())),
+            request_delay: Duration::from_millis(self.request_delay_ms
+                .clamp(*REQUEST_DELAY_RANGE.start(), *REQUEST_DELAY_RANGE.end())),
+            per_character: false,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
-enum CharacterNameStatus {
+enum CorporationStatus {
     Loading,
     Found(String),
     Error(String),
+    Cancelled,
+}
+
+// Alliances resolve exactly like corporations
+type AllianceStatus = CorporationStatus;
+
+// A destination as it would be affected by a copy, gathered without writing anything
+#[derive(Debug, Clone)]
+struct CopyPreviewEntry {
+    character_id: String,
+    path: PathBuf,
+    current_size: Option<u64>,
+    // Bytes that would change; None when either file couldn't be read
+    differing_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+struct CopyPreview {
+    source: PathBuf,
+    source_size: Option<u64>,
+    destinations: Vec<CopyPreviewEntry>,
+}
+
+// What the user clicked while the character list was drawn
+#[derive(Default)]
+struct RowActions {
+    set_source: Option<PathBuf>,
+    copy_to_add: Option<PathBuf>,
+    copy_to_remove: Option<PathBuf>,
+    retry_id: Option<String>,
+    toggle_pin: Option<String>,
+    reveal_path: Option<PathBuf>,
+    edit_note: Option<String>,
+    // Inline name editing: start on a row's file, the text as typed, and how it ended
+    edit_name: Option<PathBuf>,
+    name_text: Option<String>,
+    name_commit: bool,
+    name_cancel: bool,
+    delete_path: Option<PathBuf>,
+    toggle_details: Option<PathBuf>,
+    // Shift-clicked Copy To box: the row's file and the state to apply to the range
+    copy_to_range: Option<(PathBuf, bool)>,
+}
+
+// One file in the result dialog's table
+struct ResultRow {
+    name: String,
+    path: String,
+    result: Result<(), String>,
+    // Already matched, so nothing was written
+    identical: bool,
+}
+
+// A file from a zip backup that can be written back
+struct RestoreEntry {
+    name: String,
+    // Where the file goes; None if the zip path doesn't map onto a scanned install
+    target: Option<PathBuf>,
+    data: Vec<u8>,
+    selected: bool,
+}
+
+struct RestorePlan {
+    zip_path: PathBuf,
+    entries: Vec<RestoreEntry>,
+    // Second step: the user is looking at the final list of target paths
+    confirming: bool,
+}
+
+enum PortraitStatus {
+    Loaded(egui::TextureHandle),
+    Failed,
+}
+
+// Columns the list can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+    Filename,
+    CharacterId,
+    CharacterName,
 }
 
 // Message types for thread communication
@@ -38,303 +376,4848 @@ enum ApiMessage {
     Result {
         character_id: String,
         name: CharacterNameStatus,
+        details: CharacterDetails,
     },
+    CorporationResult {
+        corporation_id: i64,
+        name: CorporationStatus,
+        alliance_id: Option<i64>,
+    },
+    AllianceResult {
+        alliance_id: i64,
+        name: AllianceStatus,
+    },
+    Portrait {
+        character_id: String,
+        image: Result<Vec<u8>, String>,
+    },
+    ServerStatus(ServerStatus),
+}
+
+#[derive(Debug, Clone)]
+enum ServerStatus {
+    Online { players: u64 },
+    // ESI answers with an error while the game server is down
+    Offline(String),
 }
 
 struct EveSettingsApp {
     settings_files: Vec<SettingsFile>,
     character_names: HashMap<String, CharacterNameStatus>,
+    name_cache: HashMap<String, CachedName>,
+    // When each character ESI couldn't find was last looked up
+    not_found_cache: HashMap<String, u64>,
+    // Local notes by character ID; never sent anywhere
+    notes: HashMap<String, String>,
+    // Character whose note is open for editing, with the text being edited
+    editing_note: Option<(String, String)>,
+    // File whose name cell is being typed into, with the text so far
+    editing_name: Option<(PathBuf, String)>,
+    corporation_names: HashMap<i64, CorporationStatus>,
+    // Alliance of each resolved corporation; None when it isn't in one
+    corporation_alliances: HashMap<i64, Option<i64>>,
+    alliance_names: HashMap<i64, AllianceStatus>,
+    // Game server status from the latest scan; None while it is being checked
+    server_status: Option<ServerStatus>,
+    // Decoded portrait textures, kept across rescans so they're only fetched once
+    portraits: HashMap<String, PortraitStatus>,
     api_receiver: Option<Receiver<ApiMessage>>,
+    api_sender: Option<Sender<ApiMessage>>,
+    // Tripped to stop the current scan's background lookups
+    lookup_cancel: Arc<AtomicBool>,
+    // Bumped on every scan so lookups from older scans stop themselves
+    scan_generation: Arc<AtomicU64>,
+    // ESI and image server requests made since launch
+    esi_requests: Arc<AtomicU64>,
+    // Names from the user's names file, read at each scan, and why it couldn't be
+    roster_names: HashMap<String, String>,
+    roster_error: Option<String>,
     scan_complete: bool,
     eve_path: String,
+    // Text box for adding another installation
+    new_install_path: String,
+    // Informational note about how eve_path was chosen
+    path_notice: Option<String>,
     error_message: Option<String>,
+    // Folders the last scan couldn't read, so their files may be missing
+    scan_warnings: Vec<String>,
+    config: AppConfig,
+    // Rows not matching this are hidden from the list
+    search_query: String,
+    // List ordering
+    sort_key: SortKey,
+    sort_ascending: bool,
     // Copy selection state
-    copy_from: Option<String>,  // character_id of source
-    copy_to: HashSet<String>,   // character_ids of destinations
+    // Selected by path: a character can have a file in several profiles
+    copy_from: Option<PathBuf>,  // settings file of the source
+    copy_to: HashSet<PathBuf>,   // settings files of the destinations
+    // Row whose Copy To box was clicked last, where a shift-click range starts
+    last_toggled: Option<PathBuf>,
+    // Waiting for the user to confirm a copy
+    confirm_copy: bool,
+    // Pair of files waiting for the user to confirm swapping them
+    confirm_swap: Option<(PathBuf, PathBuf)>,
+    copy_options: CopyOptions,
+    // Name typed for saving the current selection as a profile
+    profile_name: String,
+    // Hide rows whose name is still loading, and optionally failed ones too
+    hide_unresolved: bool,
+    hide_errors: bool,
+    // Show the list in collapsible per-corporation sections
+    group_by_corporation: bool,
+    // Dry-run preview state
+    preview_mode: bool,
+    copy_preview: Option<CopyPreview>,
+    // Destinations saved more recently than the source, found when a copy is requested
+    copy_downgrades: Vec<PathBuf>,
+    // Files the copy would replace that are marked read-only
+    copy_read_only: Vec<PathBuf>,
+    // EVE looked like it was running when the copy was requested
+    eve_running: bool,
+    downgrade_acknowledged: bool,
+    // Zip restore in progress
+    restore_plan: Option<RestorePlan>,
+    // Settings file waiting for the user to confirm its deletion
+    pending_delete: Option<PathBuf>,
+    // Character ID typed for a settings file that doesn't exist yet, and where it goes
+    new_character_id: String,
+    new_character_folder: Option<PathBuf>,
+    // Waiting for the user to confirm creating the new file
+    confirm_create: bool,
+    // Newest file of each character found in more than one profile
+    active_files: HashSet<PathBuf>,
+    // Rows expanded to show what's inside their file
+    file_details: HashMap<PathBuf, Result<SettingsSummary, String>>,
+    // Running while auto-rescan is on and a scan has succeeded
+    watcher: Option<SettingsWatcher>,
+    // Scan running in the background, if any
+    scan_job: Option<ScanJob>,
+    // Installs the listed files were scanned from, the main one first; the path
+    // box may have been edited since
+    scanned_installs: Vec<PathBuf>,
+    // File dialog waiting for the user to pick a path, if any
+    file_dialog: Option<FileDialog>,
+    // Saved profile waiting for the user to pick which of its source's files to use
+    choose_source: Option<CopyProfile>,
+    // Help > About window
+    show_about: bool,
+    // Recent copies, newest first, and whether File > Copy History is open
+    history: Vec<HistoryEntry>,
+    show_history: bool,
+    // Source compared with the single destination, while the Diff window is open
+    settings_diff: Option<SettingsDiff>,
+    // Saves config-folder files in the background
+    writer: ConfigWriter,
     // Popup dialog state
     show_popup: bool,
+    popup_title: String,
     popup_success: bool,
     popup_message: String,
+    // Per-file results listed under the message, when there are any
+    popup_rows: Vec<ResultRow>,
 }
 
 impl Default for EveSettingsApp {
     fn default() -> Self {
+        let config = load_config();
+
+        let (eve_path, path_notice) = startup_path(config.eve_path.as_deref(), get_eve_settings_path());
+
         Self {
             settings_files: Vec::new(),
             character_names: HashMap::new(),
+            name_cache: load_name_cache(),
+            not_found_cache: load_not_found_cache(),
+            notes: load_notes(),
+            editing_note: None,
+            editing_name: None,
+            corporation_names: HashMap::new(),
+            corporation_alliances: HashMap::new(),
+            alliance_names: HashMap::new(),
+            server_status: None,
+            portraits: HashMap::new(),
             api_receiver: None,
+            api_sender: None,
+            lookup_cancel: Arc::new(AtomicBool::new(false)),
+            scan_generation: Arc::new(AtomicU64::new(0)),
+            esi_requests: Arc::new(AtomicU64::new(0)),
+            roster_names: HashMap::new(),
+            roster_error: None,
             scan_complete: false,
-            eve_path: get_eve_settings_path(),
+            eve_path,
+            new_install_path: String::new(),
+            path_notice,
             error_message: None,
+            scan_warnings: Vec::new(),
+            config,
+            search_query: String::new(),
+            sort_key: SortKey::CharacterId,
+            sort_ascending: true,
             copy_from: None,
             copy_to: HashSet::new(),
+            last_toggled: None,
+            confirm_copy: false,
+            confirm_swap: None,
+            copy_options: CopyOptions::default(),
+            profile_name: String::new(),
+            hide_unresolved: false,
+            hide_errors: false,
+            group_by_corporation: false,
+            preview_mode: false,
+            copy_downgrades: Vec::new(),
+            copy_read_only: Vec::new(),
+            eve_running: false,
+            downgrade_acknowledged: false,
+            restore_plan: None,
+            pending_delete: None,
+            new_character_id: String::new(),
+            new_character_folder: None,
+            confirm_create: false,
+            active_files: HashSet::new(),
+            file_details: HashMap::new(),
+            copy_preview: None,
+            watcher: None,
+            scan_job: None,
+            scanned_installs: Vec::new(),
+            file_dialog: None,
+            choose_source: None,
+            show_about: false,
+            history: load_history(),
+            show_history: false,
+            settings_diff: None,
+            writer: ConfigWriter::start(),
             show_popup: false,
+            popup_title: String::new(),
             popup_success: false,
             popup_message: String::new(),
+            popup_rows: Vec::new(),
         }
     }
 }
 
-fn get_eve_settings_path() -> String {
-    if let Some(home) = dirs::home_dir() {
-        let eve_path = home
-            .join(".steam/steam/steamapps/compatdata/8500/pfx/drive_c/users/steamuser/AppData/Local/CCP/EVE");
-        if eve_path.exists() {
-            return eve_path.to_string_lossy().to_string();
-        }
+// The path to show at startup, with a notice when it isn't the one the user saved.
+// Prefers the last path that scanned successfully, if it's still there.
+fn startup_path(saved: Option<&str>, default_path: Option<String>) -> (String, Option<String>) {
+    match (saved, default_path) {
+        (Some(saved), _) if PathBuf::from(saved).exists() => (saved.to_string(), None),
+        (Some(saved), Some(default_path)) => (
+            default_path,
+            Some(format!("Saved path no longer exists, using auto-detected path instead: {}", saved)),
+        ),
+        (Some(saved), None) => (
+            String::new(),
+            Some(format!("Saved path no longer exists: {}. {}", saved, NO_HOME_NOTICE)),
+        ),
+        (None, Some(default_path)) => (default_path, None),
+        (None, None) => (String::new(), Some(NO_HOME_NOTICE.to_string())),
     }
-    String::from("~/.steam/steam/steamapps/compatdata/8500/pfx/drive_c/users/steamuser/AppData/Local/CCP/EVE")
 }
 
-fn scan_for_settings_files(base_path: &str) -> Result<Vec<SettingsFile>, String> {
-    let path = PathBuf::from(base_path);
-    
-    if !path.exists() {
-        return Err(format!("Path does not exist: {}", base_path));
-    }
-
-    let mut files = Vec::new();
-    let char_regex = Regex::new(r"^core_char_(\d+)\.dat$").unwrap();
-
-    // Walk through the EVE directory to find settings folders
-    if let Ok(entries) = fs::read_dir(&path) {
-        for entry in entries.flatten() {
-            let entry_path = entry.path();
-            if entry_path.is_dir() {
-                // Look for settings_Default folder
-                if let Ok(sub_entries) = fs::read_dir(&entry_path) {
-                    for sub_entry in sub_entries.flatten() {
-                        let sub_path = sub_entry.path();
-                        if sub_path.is_dir() && sub_path.file_name()
-                            .map(|n| n.to_string_lossy().starts_with("settings_"))
-                            .unwrap_or(false)
-                        {
-                            // Scan this settings folder for character files only
-                            if let Ok(settings_files) = fs::read_dir(&sub_path) {
-                                for file_entry in settings_files.flatten() {
-                                    let file_path = file_entry.path();
-                                    if let Some(filename) = file_path.file_name() {
-                                        let filename_str = filename.to_string_lossy().to_string();
-                                        
-                                        if let Some(caps) = char_regex.captures(&filename_str) {
-                                            let char_id = caps[1].to_string();
-                                            files.push(SettingsFile {
-                                                path: file_path,
-                                                filename: filename_str,
-                                                character_id: char_id,
-                                                character_name: CharacterNameStatus::Loading,
-                                            });
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
+// EVE's settings folder inside a Wine prefix. The Windows user is named after
+// whoever created the prefix ("steamuser" under Proton), so every user is tried.
+fn wine_prefix_eve_path(prefix: &Path) -> Option<PathBuf> {
+    let mut users: Vec<PathBuf> = fs::read_dir(prefix.join("drive_c/users")).ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    users.sort();
+    users.into_iter()
+        .map(|user| user.join("AppData/Local/CCP/EVE"))
+        .find(|path| path.is_dir())
+}
 
-    // Sort files by character ID
-    files.sort_by(|a, b| a.character_id.cmp(&b.character_id));
+// Places under the home directory EVE may be installed, most specific first
+const EVE_PATH_CANDIDATES: &[fn(&Path) -> Option<PathBuf>] = &[
+    |home| wine_prefix_eve_path(&home.join(".steam/steam/steamapps/compatdata/8500/pfx")),
+    |home| wine_prefix_eve_path(&home.join(".var/app/com.valvesoftware.Steam/.steam/steam/steamapps/compatdata/8500/pfx")),
+    // Lutris installs into ~/Games/<game slug> by default
+    |home| wine_prefix_eve_path(&home.join("Games/eve-online")),
+    |home| wine_prefix_eve_path(&home.join("Games/eve")),
+    |home| wine_prefix_eve_path(&home.join(".wine")),
+];
 
-    Ok(files)
+fn get_eve_settings_path() -> Option<String> {
+    let wine_prefix = std::env::var_os("WINEPREFIX").map(PathBuf::from);
+    eve_settings_path_from(dirs::home_dir().as_deref(), wine_prefix.as_deref())
 }
 
-fn fetch_character_name(character_id: &str) -> CharacterNameStatus {
-    let url = format!("https://esi.evetech.net/latest/characters/{}/?datasource=tranquility", character_id);
-    
-    let client = reqwest::blocking::Client::builder()
-        .timeout(Duration::from_secs(10))
-        .build();
-
-    match client {
-        Ok(client) => {
-            match client.get(&url).send() {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        match response.json::<EsiCharacterResponse>() {
-                            Ok(data) => CharacterNameStatus::Found(data.name),
-                            Err(e) => CharacterNameStatus::Error(format!("Parse error: {}", e)),
-                        }
-                    } else if response.status().as_u16() == 404 {
-                        CharacterNameStatus::Error("Character not found".to_string())
-                    } else {
-                        CharacterNameStatus::Error(format!("HTTP {}", response.status()))
-                    }
-                }
-                Err(e) => CharacterNameStatus::Error(format!("Request failed: {}", e)),
-            }
-        }
-        Err(e) => CharacterNameStatus::Error(format!("Client error: {}", e)),
+// The first existing candidate location, falling back to the Steam/Proton default.
+// An explicitly chosen Wine prefix wins over any default location, and doesn't
+// need a home directory. Otherwise None when there is no home directory to look
+// under (e.g. minimal containers) and the user has to pick a path.
+fn eve_settings_path_from(home: Option<&Path>, wine_prefix: Option<&Path>) -> Option<String> {
+    if let Some(eve_path) = wine_prefix.and_then(wine_prefix_eve_path) {
+        return Some(eve_path.to_string_lossy().to_string());
     }
+    let home = home?;
+    let eve_path = EVE_PATH_CANDIDATES.iter()
+        .find_map(|candidate| candidate(home))
+        .unwrap_or_else(|| home
+            .join(".steam/steam/steamapps/compatdata/8500/pfx/drive_c/users/steamuser/AppData/Local/CCP/EVE"));
+    Some(eve_path.to_string_lossy().to_string())
 }
 
-fn start_api_lookups(character_ids: Vec<String>, sender: Sender<ApiMessage>) {
-    thread::spawn(move || {
-        // Deduplicate character IDs
-        let mut unique_ids: Vec<String> = character_ids.clone();
-        unique_ids.sort();
-        unique_ids.dedup();
+const NO_HOME_NOTICE: &str =
+    "No home directory was found, so the default Steam path can't be used. Enter your EVE settings path above or click Browse…";
 
-        for (i, char_id) in unique_ids.iter().enumerate() {
-            // Add small delay between requests to be polite to the API
-            if i > 0 {
-                thread::sleep(Duration::from_millis(500));
-            }
+// The caches in the config folder; core takes the folder so it can work anywhere.
+// The window saves them through its ConfigWriter, and only the command line
+// writes them directly.
+fn load_name_cache() -> HashMap<String, CachedName> {
+    get_config_dir().map(|dir| crate::core::load_name_cache(&dir)).unwrap_or_default()
+}
 
-            let name_status = fetch_character_name(char_id);
-            let _ = sender.send(ApiMessage::Result {
-                character_id: char_id.clone(),
-                name: name_status,
-            });
-        }
-    });
+fn save_name_cache(cache: &HashMap<String, CachedName>) -> Result<(), String> {
+    let dir = get_config_dir().ok_or("No config directory available")?;
+    crate::core::save_name_cache(&dir, cache)
 }
 
-impl EveSettingsApp {
-    fn scan_files(&mut self) {
-        match scan_for_settings_files(&self.eve_path) {
-            Ok(files) => {
-                self.settings_files = files;
-                self.error_message = None;
+fn load_not_found_cache() -> HashMap<String, u64> {
+    get_config_dir().map(|dir| crate::core::load_not_found_cache(&dir)).unwrap_or_default()
+}
 
-                // Collect unique character IDs for API lookups
-                let char_ids: Vec<String> = self.settings_files
-                    .iter()
-                    .map(|f| f.character_id.clone())
-                    .collect();
+fn save_not_found_cache(cache: &HashMap<String, u64>) -> Result<(), String> {
+    let dir = get_config_dir().ok_or("No config directory available")?;
+    crate::core::save_not_found_cache(&dir, cache)
+}
 
-                // Initialize all as loading
-                for id in &char_ids {
-                    self.character_names.insert(id.clone(), CharacterNameStatus::Loading);
-                }
+fn load_config() -> AppConfig {
+    let path = match get_config_dir() {
+        Some(dir) => dir.join("config.json"),
+        None => return AppConfig::default(),
+    };
 
-                // Start background API lookups
-                let (sender, receiver) = channel();
-                self.api_receiver = Some(receiver);
-                start_api_lookups(char_ids, sender);
-            }
-            Err(e) => {
-                self.error_message = Some(e);
-            }
-        }
-        self.scan_complete = true;
-    }
+    fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
 
-    fn process_api_messages(&mut self) {
-        if let Some(receiver) = &self.api_receiver {
-            while let Ok(msg) = receiver.try_recv() {
-                match msg {
-                    ApiMessage::Result { character_id, name } => {
-                        self.character_names.insert(character_id.clone(), name.clone());
-                        // Update all files with this character ID
-                        for file in &mut self.settings_files {
-                            if file.character_id == character_id {
-                                file.character_name = name.clone();
-                            }
+// Replace one file in the config folder whole, so a crash or a second save can
+// never leave it half written
+fn write_config_file(file_name: &str, contents: &[u8]) -> Result<(), String> {
+    let dir = get_config_dir().ok_or("No config directory available")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    write_atomic(&dir.join(file_name), contents)
+        .map_err(|e| format!("Failed to write {}: {}", file_name, e))
+}
+
+enum ConfigWrite {
+    Save { file_name: &'static str, contents: String },
+    // One operation log entry; these are appended in the order sent
+    Log(String),
+    // Answered once everything sent before it has been written
+    Flush(Sender<()>),
+}
+
+// Writes config-folder files on one long-lived thread, in the order they were
+// asked for, so two saves (or a log append and the log's rotation) can't
+// interleave. Saves of the same file still waiting their turn are coalesced to
+// the latest, which keeps typing cheap.
+struct ConfigWriter {
+    sender: Sender<ConfigWrite>,
+}
+
+impl ConfigWriter {
+    fn start() -> Self {
+        let (sender, receiver) = channel::<ConfigWrite>();
+        thread::spawn(move || {
+            while let Ok(first) = receiver.recv() {
+                let mut saves: Vec<(&'static str, String)> = Vec::new();
+                let mut entries = Vec::new();
+                let mut flushes = Vec::new();
+                for write in std::iter::once(first).chain(receiver.try_iter()) {
+                    match write {
+                        ConfigWrite::Save { file_name, contents } => {
+                            saves.retain(|(queued, _)| *queued != file_name);
+                            saves.push((file_name, contents));
                         }
+                        ConfigWrite::Log(entry) => entries.push(entry),
+                        ConfigWrite::Flush(done) => flushes.push(done),
                     }
                 }
+                // Persistence and logging are best-effort
+                for (file_name, contents) in saves {
+                    let _ = write_config_file(file_name, contents.as_bytes());
+                }
+                for entry in entries {
+                    let _ = write_operation_log(&entry);
+                }
+                for done in flushes {
+                    let _ = done.send(());
+                }
             }
+        });
+        Self { sender }
+    }
+
+    fn save<T: Serialize + ?Sized>(&self, file_name: &'static str, value: &T) {
+        if let Ok(contents) = serde_json::to_string_pretty(value) {
+            let _ = self.sender.send(ConfigWrite::Save { file_name, contents });
         }
     }
 
-    fn copy_settings(&mut self) {
-        let source_id = match &self.copy_from {
-            Some(id) => id.clone(),
-            None => {
-                self.popup_message = "No source selected".to_string();
-                self.popup_success = false;
-                self.show_popup = true;
-                return;
-            }
-        };
+    fn log(&self, entry: String) {
+        let _ = self.sender.send(ConfigWrite::Log(entry));
+    }
 
-        if self.copy_to.is_empty() {
-            self.popup_message = "No destinations selected".to_string();
-            self.popup_success = false;
-            self.show_popup = true;
-            return;
+    // Wait, briefly, for everything queued so far to reach the disk
+    fn flush(&self) {
+        let (done, finished) = channel();
+        if self.sender.send(ConfigWrite::Flush(done)).is_ok() {
+            let _ = finished.recv_timeout(Duration::from_secs(5));
         }
+    }
+}
 
-        // Find the source file
-        let source_file = self.settings_files.iter().find(|f| f.character_id == source_id);
-        let source_path = match source_file {
-            Some(f) => f.path.clone(),
-            None => {
-                self.popup_message = "Source file not found".to_string();
-                self.popup_success = false;
-                self.show_popup = true;
-                return;
-            }
-        };
+// Notes the user keeps per character ID, e.g. "main" or "hauler"
+fn load_notes() -> HashMap<String, String> {
+    let path = match get_config_dir() {
+        Some(dir) => dir.join("notes.json"),
+        None => return HashMap::new(),
+    };
 
-        // Read source file contents
-        let source_contents = match fs::read(&source_path) {
-            Ok(contents) => contents,
-            Err(e) => {
-                self.popup_message = format!("Failed to read source: {}", e);
-                self.popup_success = false;
-                self.show_popup = true;
-                return;
-            }
-        };
+    fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
 
-        // Copy to each destination
-        let mut success_count = 0;
-        let mut error_messages: Vec<String> = Vec::new();
+// Recent copies kept for re-selecting from File > Copy History
+const HISTORY_MAX_ENTRIES: usize = 30;
 
-        for dest_id in &self.copy_to {
-            let dest_file = self.settings_files.iter().find(|f| f.character_id == *dest_id);
-            if let Some(dest) = dest_file {
-                match fs::write(&dest.path, &source_contents) {
-                    Ok(_) => success_count += 1,
-                    Err(e) => error_messages.push(format!("{}: {}", dest_id, e)),
-                }
-            }
-        }
+// One copy as it was selected, with the names shown at the time so entries
+// stay readable even if a file has since gone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HistoryEntry {
+    // Log-style UTC timestamp
+    time: String,
+    // Who ran it, for machines shared between players
+    #[serde(default)]
+    user: String,
+    source: PathBuf,
+    source_name: String,
+    destinations: Vec<(PathBuf, String)>,
+}
 
-        if error_messages.is_empty() {
-            self.popup_message = format!("Successfully copied settings to {} character(s)", success_count);
-            self.popup_success = true;
-        } else {
-            self.popup_message = format!("Copied to {} character(s), but {} failed: {}", 
-                success_count, error_messages.len(), error_messages.join(", "));
-            self.popup_success = false;
-        }
-        self.show_popup = true;
+// The account name as the OS reports it, if any
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
 
-        // Clear selections after copy
-        self.copy_from = None;
-        self.copy_to.clear();
+// Newest first
+fn load_history() -> Vec<HistoryEntry> {
+    let path = match get_config_dir() {
+        Some(dir) => dir.join("history.json"),
+        None => return Vec::new(),
+    };
+
+    fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+// The operation log is rotated to operations.log.1 once it grows past this
+const OPERATION_LOG_MAX_BYTES: u64 = 1024 * 1024;
+
+fn operation_log_path() -> Option<PathBuf> {
+    get_config_dir().map(|dir| dir.join("operations.log"))
+}
+
+// Timestamp for log entries, e.g. "2025-01-14 18:03:12" (UTC)
+fn log_timestamp() -> String {
+    let secs = unix_now() as i64;
+    let (year, month, day) = civil_from_days(secs / 86_400);
+    let secs_of_day = secs % 86_400;
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day,
+        secs_of_day / 3600, (secs_of_day % 3600) / 60, secs_of_day % 60)
+}
+
+// Append one entry to the operation log, rotating it first if it has grown too big
+fn write_operation_log(entry: &str) -> Result<(), String> {
+    let path = operation_log_path().ok_or("No config directory available")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
     }
 
-    fn can_copy(&self) -> bool {
-        self.copy_from.is_some() && !self.copy_to.is_empty()
+    if fs::metadata(&path).map(|m| m.len() > OPERATION_LOG_MAX_BYTES).unwrap_or(false) {
+        let _ = fs::rename(&path, path.with_extension("log.1"));
     }
-}
 
-impl eframe::App for EveSettingsApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Process any pending API messages
-        self.process_api_messages();
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open log: {}", e))?;
+    writeln!(file, "[{} UTC] {}", log_timestamp(), entry)
+        .map_err(|e| format!("Failed to write log: {}", e))
+}
 
-        // Request repaint while loading
-        let has_loading = self.character_names.values().any(|v| matches!(v, CharacterNameStatus::Loading));
-        if has_loading {
-            ctx.request_repaint_after(Duration::from_millis(100));
+// One log line per touched file, indented under the operation's header
+fn log_file_results(header: String, results: &[(String, Result<(), String>)]) -> String {
+    let mut entry = header;
+    for (target, result) in results {
+        match result {
+            Ok(()) => entry.push_str(&format!("\n    ok      {}", target)),
+            Err(e) => entry.push_str(&format!("\n    failed  {}: {}", target, e)),
         }
+    }
+    entry
+}
 
-        // Configure custom styling - matching ToonTab colour scheme
-        // ToonTab uses the default egui dark theme, so we just ensure dark mode
-        let mut style = (*ctx.style()).clone();
-        style.visuals = egui::Visuals::dark();
-        ctx.set_style(style);
+fn copy_log_entry(source: &str, outcome: &CopyOutcome) -> String {
+    log_file_results(format!("copy from {}", source), &outcome.file_results)
+}
+
+// How often the watcher looks at the settings tree. Changes must also stop for
+// this long before a rescan, so a burst of writes causes only one.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+// Every character file under the given paths and when it last changed
+fn settings_fingerprint(roots: &[String], max_depth: usize, stop: &AtomicBool) -> Vec<(PathBuf, Option<SystemTime>, Option<u64>)> {
+    let mut fingerprint: Vec<_> = roots.iter()
+        .filter_map(|root| scan_for_settings_files(root, max_depth, stop).ok())
+        .flat_map(|(files, _)| files)
+        .map(|f| (f.path, f.modified, f.size))
+        .collect();
+    fingerprint.sort();
+    fingerprint
+}
+
+// Polls the scanned paths on a background thread; there's no file-watching
+// crate to lean on, and a settings tree is small enough to walk each second
+struct SettingsWatcher {
+    roots: Vec<String>,
+    stop: Arc<AtomicBool>,
+    // One message per settled change
+    receiver: Receiver<()>,
+}
+
+impl SettingsWatcher {
+    fn start(roots: Vec<String>, max_depth: usize, ctx: egui::Context) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = channel();
+        let watched = roots.clone();
+        let stopped = stop.clone();
+        thread::spawn(move || {
+            let mut last = settings_fingerprint(&watched, max_depth, &stopped);
+            let mut changed = false;
+            loop {
+                thread::sleep(WATCH_INTERVAL);
+                if stopped.load(Ordering::Relaxed) {
+                    return;
+                }
+                let current = settings_fingerprint(&watched, max_depth, &stopped);
+                if current != last {
+                    last = current;
+                    changed = true;
+                } else if changed {
+                    changed = false;
+                    if sender.send(()).is_err() {
+                        return;
+                    }
+                    ctx.request_repaint();
+                }
+            }
+        });
+        Self { roots, stop, receiver }
+    }
+}
+
+impl Drop for SettingsWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+// A scan running on a background thread, so a slow disk doesn't freeze the
+// window. Dropping it cancels the walk and discards whatever it found.
+struct ScanJob {
+    cancel: Arc<AtomicBool>,
+    receiver: Receiver<Result<InstallScan, String>>,
+}
+
+impl ScanJob {
+    fn start(roots: Vec<String>, max_depth: usize) -> Self {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = channel();
+        let cancelled = cancel.clone();
+        thread::spawn(move || {
+            let result = scan_installs(&roots, max_depth, &cancelled);
+            if !cancelled.load(Ordering::Relaxed) {
+                let _ = sender.send(result);
+            }
+        });
+        Self { cancel, receiver }
+    }
+}
+
+impl Drop for ScanJob {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+// Short label for an installation's base path
+fn install_label(install: &Path) -> String {
+    let path = install.to_string_lossy();
+    if path.contains("steamapps/compatdata") {
+        return "Steam".to_string();
+    }
+    match dirs::home_dir() {
+        Some(home) if install.starts_with(&home) => {
+            format!("~/{}", install.strip_prefix(&home).unwrap_or(install).display())
+        }
+        _ => path.to_string(),
+    }
+}
+
+// Green used for resolved names and successes, darker on light backgrounds to stay legible
+fn success_color(ui: &egui::Ui) -> egui::Color32 {
+    if ui.visuals().dark_mode {
+        egui::Color32::from_rgb(100, 200, 100)
+    } else {
+        egui::Color32::from_rgb(0, 128, 0)
+    }
+}
+
+fn format_size(size: Option<u64>) -> String {
+    match size {
+        Some(bytes) => format!("{} bytes", bytes),
+        None => "unknown".to_string(),
+    }
+}
+
+// Human-readable size such as "1.3 MB", for totals too big to read in bytes
+fn format_size_short(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["bytes", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} bytes", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+// EVE writes settings with its own marshal format, which always starts with this byte
+const MARSHAL_HEADER: u8 = 0x7E;
+// Shorter printable runs are mostly coincidences inside binary data
+const MIN_SUMMARY_STRING_LEN: usize = 4;
+const MAX_SUMMARY_STRINGS: usize = 200;
+
+// What can be read out of a settings file without understanding its full structure
+#[derive(Debug, Clone)]
+struct SettingsSummary {
+    size: usize,
+    marshal: bool,
+    // Distinct readable text in file order, e.g. window and overview profile names
+    strings: Vec<String>,
+    // Bytes that aren't part of any of the text above
+    binary_bytes: usize,
+}
+
+fn summarize_settings(contents: &[u8]) -> SettingsSummary {
+    let mut strings: Vec<String> = Vec::new();
+    let mut seen: HashSet<&[u8]> = HashSet::new();
+    let mut text_bytes = 0;
+
+    let is_text = |b: &u8| (0x20..0x7F).contains(b);
+    let mut rest = contents;
+    while !rest.is_empty() {
+        let start = rest.iter().position(is_text).unwrap_or(rest.len());
+        rest = &rest[start..];
+        let len = rest.iter().position(|b| !is_text(b)).unwrap_or(rest.len());
+        let run = &rest[..len];
+        rest = &rest[len..];
+
+        // Runs without a letter are usually packed numbers
+        if run.len() >= MIN_SUMMARY_STRING_LEN && run.iter().any(u8::is_ascii_alphabetic) {
+            text_bytes += run.len();
+            if seen.insert(run) && strings.len() < MAX_SUMMARY_STRINGS {
+                strings.push(String::from_utf8_lossy(run).to_string());
+            }
+        }
+    }
+
+    SettingsSummary {
+        size: contents.len(),
+        marshal: contents.first() == Some(&MARSHAL_HEADER),
+        strings,
+        binary_bytes: contents.len() - text_bytes,
+    }
+}
+
+// Bytes that change when `dest` is overwritten with `source`; any length
+// difference counts in full
+fn count_differing_bytes(source: &[u8], dest: &[u8]) -> u64 {
+    let changed = source.iter().zip(dest).filter(|(a, b)| a != b).count();
+    (changed + source.len().abs_diff(dest.len())) as u64
+}
+
+// Differences closer together than this are shown as one region
+const DIFF_MERGE_GAP: usize = 8;
+const MAX_DIFF_REGIONS: usize = 200;
+// Bytes of each side shown next to a region
+const DIFF_PREVIEW_BYTES: usize = 16;
+
+// A run of bytes that differs between the two files; past the end of the
+// shorter file the region covers the extra bytes of the longer one
+#[derive(Debug, Clone)]
+struct DiffRegion {
+    offset: usize,
+    len: usize,
+    source_preview: String,
+    dest_preview: String,
+}
+
+// Where two settings files differ, for the Diff window
+#[derive(Debug, Clone)]
+struct SettingsDiff {
+    source_name: String,
+    dest_name: String,
+    source_size: usize,
+    dest_size: usize,
+    differing_bytes: u64,
+    // Readable text found in only one of the two files
+    only_in_source: Vec<String>,
+    only_in_dest: Vec<String>,
+    regions: Vec<DiffRegion>,
+    // Regions left out once MAX_DIFF_REGIONS is reached
+    more_regions: usize,
+}
+
+// Hex of up to DIFF_PREVIEW_BYTES starting at `offset`, or "-" past the end
+fn hex_preview(contents: &[u8], offset: usize) -> String {
+    if offset >= contents.len() {
+        return "-".to_string();
+    }
+    let end = (offset + DIFF_PREVIEW_BYTES).min(contents.len());
+    contents[offset..end].iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ")
+}
+
+fn diff_settings(source_name: String, dest_name: String, source: &[u8], dest: &[u8]) -> SettingsDiff {
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    for (offset, _) in source.iter().zip(dest).enumerate().filter(|(_, (a, b))| a != b) {
+        match spans.last_mut() {
+            Some((start, len)) if offset - (*start + *len) < DIFF_MERGE_GAP => *len = offset + 1 - *start,
+            _ => spans.push((offset, 1)),
+        }
+    }
+    let common = source.len().min(dest.len());
+    if source.len() != dest.len() {
+        match spans.last_mut() {
+            Some((start, len)) if common - (*start + *len) < DIFF_MERGE_GAP => {
+                *len = source.len().max(dest.len()) - *start;
+            }
+            _ => spans.push((common, source.len().abs_diff(dest.len()))),
+        }
+    }
+
+    let more_regions = spans.len().saturating_sub(MAX_DIFF_REGIONS);
+    let regions = spans.into_iter()
+        .take(MAX_DIFF_REGIONS)
+        .map(|(offset, len)| DiffRegion {
+            offset,
+            len,
+            source_preview: hex_preview(source, offset),
+            dest_preview: hex_preview(dest, offset),
+        })
+        .collect();
+
+    let source_strings = summarize_settings(source).strings;
+    let dest_strings = summarize_settings(dest).strings;
+    let only_in = |a: &[String], b: &[String]| -> Vec<String> {
+        let other: HashSet<&String> = b.iter().collect();
+        a.iter().filter(|s| !other.contains(s)).cloned().collect()
+    };
+
+    SettingsDiff {
+        source_name,
+        dest_name,
+        source_size: source.len(),
+        dest_size: dest.len(),
+        differing_bytes: count_differing_bytes(source, dest),
+        only_in_source: only_in(&source_strings, &dest_strings),
+        only_in_dest: only_in(&dest_strings, &source_strings),
+        regions,
+        more_regions,
+    }
+}
+
+// Days since the Unix epoch of a civil date; the inverse of civil_from_days
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Parse an HTTP date such as "Wed, 21 Oct 2026 07:28:00 GMT" into Unix seconds
+fn parse_http_date(date: &str) -> Option<u64> {
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun",
+        "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let mut parts = date.split_whitespace().skip(1);
+    let day: u32 = parts.next()?.parse().ok()?;
+    let month = MONTHS.iter().position(|m| Some(*m) == parts.next())? as u32 + 1;
+    let year: i64 = parts.next()?.parse().ok()?;
+    let mut time = parts.next()?.split(':').map(|part| part.parse::<i64>().ok());
+    let (hours, minutes, seconds) = (time.next()??, time.next()??, time.next()??);
+    let secs = days_from_civil(year, month, day) * 86_400 + hours * 3600 + minutes * 60 + seconds;
+    u64::try_from(secs).ok()
+}
+
+// Format a timestamp as "YYYY-MM-DD HH:MM" in UTC (EVE time)
+fn format_timestamp(time: Option<SystemTime>) -> String {
+    let secs = match time.and_then(|t| t.duration_since(UNIX_EPOCH).ok()) {
+        Some(d) => d.as_secs() as i64,
+        None => return "unknown".to_string(),
+    };
+
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let secs_of_day = secs.rem_euclid(86_400);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}",
+        year, month, day, secs_of_day / 3600, (secs_of_day % 3600) / 60)
+}
+
+// How long ago a timestamp was, e.g. "3 days ago"; times ahead of the clock count as now
+fn format_relative_time(time: Option<SystemTime>) -> String {
+    let Some(time) = time else {
+        return "unknown".to_string();
+    };
+    let secs = SystemTime::now().duration_since(time).map(|d| d.as_secs()).unwrap_or(0);
+    let (count, unit) = match secs {
+        0..=59 => return "just now".to_string(),
+        60..=3_599 => (secs / 60, "minute"),
+        3_600..=86_399 => (secs / 3_600, "hour"),
+        86_400..=2_591_999 => (secs / 86_400, "day"),
+        2_592_000..=31_535_999 => (secs / 2_592_000, "month"),
+        _ => (secs / 31_536_000, "year"),
+    };
+    format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" })
+}
+
+// Parse the date part of an ISO 8601 timestamp such as "2015-03-24T11:37:00Z"
+fn parse_iso8601_date(timestamp: &str) -> Option<(i64, u32, u32)> {
+    let date = timestamp.get(..10)?;
+    let mut parts = date.split('-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+    Some((year, month, day))
+}
+
+// Age from an ESI birthday to today, e.g. "11y 3m"
+fn format_character_age(birthday: Option<&str>) -> String {
+    let (birth_year, birth_month, birth_day) = match birthday.and_then(parse_iso8601_date) {
+        Some(date) => date,
+        None => return "—".to_string(),
+    };
+    let (year, month, day) = civil_from_days(unix_now() as i64 / 86_400);
+
+    let mut months = (year - birth_year) * 12 + month as i64 - birth_month as i64;
+    if day < birth_day {
+        months -= 1;
+    }
+    if months < 0 {
+        return "—".to_string();
+    }
+    format!("{}y {}m", months / 12, months % 12)
+}
+
+// Quote a CSV field when it contains a delimiter, quote, or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn build_csv(files: &[SettingsFile]) -> String {
+    let mut csv = String::from("filename,character_id,character_name,profile,note\n");
+
+    for file in files {
+        let (name, note) = match &file.character_name {
+            CharacterNameStatus::Found(name) => (name.as_str(), ""),
+            CharacterNameStatus::Error(err) => (err.as_str(), "lookup failed"),
+            CharacterNameStatus::Loading => ("", "lookup pending"),
+            CharacterNameStatus::Retrying => ("", "lookup pending (rate limited)"),
+            CharacterNameStatus::Cancelled => ("", "lookup cancelled"),
+            CharacterNameStatus::Offline => ("", "offline"),
+            CharacterNameStatus::Deleted => ("", "character not found (deleted?)"),
+        };
+        let row = [file.filename.as_str(), file.character_id.as_str(), name, file.profile.as_str(), note]
+            .iter()
+            .map(|field| csv_field(field))
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push_str(&row);
+        csv.push('\n');
+    }
+
+    csv
+}
+
+// What a file dialog was opened for, so its answer goes to the right place
+#[derive(Clone, Copy)]
+enum FileDialogPurpose {
+    EvePath,
+    AddInstall,
+    NamesFile,
+    ExportCsv,
+    BackupAll,
+    Restore,
+}
+
+impl FileDialogPurpose {
+    fn title(self) -> &'static str {
+        match self {
+            FileDialogPurpose::EvePath | FileDialogPurpose::AddInstall | FileDialogPurpose::NamesFile => "Browse",
+            FileDialogPurpose::ExportCsv => "Export CSV",
+            FileDialogPurpose::BackupAll => "Backup All",
+            FileDialogPurpose::Restore => "Restore",
+        }
+    }
+}
+
+// A file dialog running on a background thread, so the window keeps drawing
+// while it's open. The chosen path is picked up by poll_file_dialog.
+struct FileDialog {
+    purpose: FileDialogPurpose,
+    receiver: Receiver<Result<Option<PathBuf>, String>>,
+}
+
+impl FileDialog {
+    fn start(purpose: FileDialogPurpose, zenity_args: Vec<String>, kdialog_args: Vec<String>) -> Self {
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let _ = sender.send(run_file_dialog(&zenity_args, &kdialog_args));
+        });
+        Self { purpose, receiver }
+    }
+}
+
+// Run a native file dialog through zenity, falling back to kdialog.
+// Returns Ok(None) when the user cancels the dialog.
+fn run_file_dialog(zenity_args: &[String], kdialog_args: &[String]) -> Result<Option<PathBuf>, String> {
+    let dialogs = [("zenity", zenity_args), ("kdialog", kdialog_args)];
+
+    for (program, args) in dialogs {
+        match Command::new(program).args(args).output() {
+            Ok(output) => {
+                if !output.status.success() {
+                    return Ok(None);
+                }
+                let chosen = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                if chosen.is_empty() {
+                    return Ok(None);
+                }
+                return Ok(Some(PathBuf::from(chosen)));
+            }
+            // Not installed, try the next one
+            Err(_) => continue,
+        }
+    }
+
+    Err("No file dialog available - please install zenity or kdialog".to_string())
+}
+
+fn to_args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn pick_folder(purpose: FileDialogPurpose, start_dir: &str) -> FileDialog {
+    let zenity_filename = format!("--filename={}/", start_dir.trim_end_matches('/'));
+    FileDialog::start(
+        purpose,
+        to_args(&["--file-selection", "--directory", &zenity_filename]),
+        to_args(&["--getexistingdirectory", start_dir]),
+    )
+}
+
+fn pick_zip_file() -> FileDialog {
+    FileDialog::start(
+        FileDialogPurpose::Restore,
+        to_args(&["--file-selection", "--file-filter=Zip archives | *.zip"]),
+        to_args(&["--getopenfilename", ".", "*.zip"]),
+    )
+}
+
+fn pick_names_file() -> FileDialog {
+    FileDialog::start(
+        FileDialogPurpose::NamesFile,
+        to_args(&["--file-selection", "--file-filter=JSON files | *.json"]),
+        to_args(&["--getopenfilename", ".", "*.json"]),
+    )
+}
+
+fn pick_save_file(purpose: FileDialogPurpose, default_name: &str) -> FileDialog {
+    let zenity_filename = format!("--filename={}", default_name);
+    FileDialog::start(
+        purpose,
+        to_args(&["--file-selection", "--save", "--confirm-overwrite", &zenity_filename]),
+        to_args(&["--getsavefilename", default_name]),
+    )
+}
+
+// A file stored in (or read from) a zip backup
+struct ZipEntry {
+    // Forward-slash separated path inside the archive
+    name: String,
+    data: Vec<u8>,
+    modified: Option<SystemTime>,
+}
+
+// Zip's local-header flag marking file names as UTF-8
+const ZIP_UTF8_FLAG: u16 = 0x0800;
+const ZIP_METHOD_STORE: u16 = 0;
+const ZIP_METHOD_DEFLATE: u16 = 8;
+
+// Zip stores local DOS time with two-second resolution, starting in 1980
+fn dos_date_time(time: Option<SystemTime>) -> (u16, u16) {
+    let secs = time
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    if year < 1980 {
+        // 1980-01-01 00:00
+        return (0, (1 << 5) | 1);
+    }
+    let secs_of_day = secs.rem_euclid(86_400);
+    let time = ((secs_of_day / 3600) << 11) | (((secs_of_day % 3600) / 60) << 5) | ((secs_of_day % 60) / 2);
+    let date = ((year - 1980) << 9) | ((month as i64) << 5) | day as i64;
+    (time as u16, date as u16)
+}
+
+// Build a deflated zip archive in memory. Settings files are small, so there is
+// no need for zip64 or streaming.
+fn build_zip(entries: &[ZipEntry]) -> io::Result<Vec<u8>> {
+    let mut archive = Vec::new();
+    let mut central_directory = Vec::new();
+
+    for entry in entries {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&entry.data)?;
+        let compressed = encoder.finish()?;
+
+        let crc = crc32fast::hash(&entry.data);
+        let (time, date) = dos_date_time(entry.modified);
+        let name = entry.name.as_bytes();
+        let offset = archive.len() as u32;
+
+        // Fields shared by the local header and the central directory record
+        let mut common = Vec::new();
+        common.extend_from_slice(&20u16.to_le_bytes()); // version needed to extract
+        common.extend_from_slice(&ZIP_UTF8_FLAG.to_le_bytes());
+        common.extend_from_slice(&ZIP_METHOD_DEFLATE.to_le_bytes());
+        common.extend_from_slice(&time.to_le_bytes());
+        common.extend_from_slice(&date.to_le_bytes());
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(entry.data.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+
+        archive.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        archive.extend_from_slice(&common);
+        archive.extend_from_slice(name);
+        archive.extend_from_slice(&compressed);
+
+        central_directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        central_directory.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        central_directory.extend_from_slice(&common);
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // disk number
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+        central_directory.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+        central_directory.extend_from_slice(&offset.to_le_bytes());
+        central_directory.extend_from_slice(name);
+    }
+
+    let directory_offset = archive.len() as u32;
+    archive.extend_from_slice(&central_directory);
+
+    // End of central directory record
+    archive.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes()); // this disk
+    archive.extend_from_slice(&0u16.to_le_bytes()); // disk with the directory
+    archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&directory_offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes()); // comment length
+    Ok(archive)
+}
+
+// Read the files out of a zip archive, as written by build_zip or any common
+// tool that stores or deflates its entries
+fn read_zip(archive: &[u8]) -> Result<Vec<ZipEntry>, String> {
+    let u16_at = |pos: usize| archive.get(pos..pos + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize);
+    let u32_at = |pos: usize| archive.get(pos..pos + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+    let corrupt = || "Zip file is damaged or not a zip file".to_string();
+
+    // The end of central directory record sits at the very end, before an optional comment
+    let end = (0..archive.len().saturating_sub(21))
+        .rev()
+        .find(|&pos| u32_at(pos) == Some(0x0605_4b50))
+        .ok_or_else(corrupt)?;
+    let count = u16_at(end + 10).ok_or_else(corrupt)?;
+    let mut pos = u32_at(end + 16).ok_or_else(corrupt)?;
+
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        if u32_at(pos) != Some(0x0201_4b50) {
+            return Err(corrupt());
+        }
+        let method = u16_at(pos + 10).ok_or_else(corrupt)? as u16;
+        let crc = u32_at(pos + 16).ok_or_else(corrupt)? as u32;
+        let compressed_size = u32_at(pos + 20).ok_or_else(corrupt)?;
+        let name_len = u16_at(pos + 28).ok_or_else(corrupt)?;
+        let extra_len = u16_at(pos + 30).ok_or_else(corrupt)?;
+        let comment_len = u16_at(pos + 32).ok_or_else(corrupt)?;
+        let local = u32_at(pos + 42).ok_or_else(corrupt)?;
+        let name = archive.get(pos + 46..pos + 46 + name_len).ok_or_else(corrupt)?;
+        let name = String::from_utf8_lossy(name).to_string();
+        pos += 46 + name_len + extra_len + comment_len;
+
+        // Folders have no contents of their own
+        if name.ends_with('/') {
+            continue;
+        }
+
+        let data_start = local + 30
+            + u16_at(local + 26).ok_or_else(corrupt)?
+            + u16_at(local + 28).ok_or_else(corrupt)?;
+        let raw = archive.get(data_start..data_start + compressed_size).ok_or_else(corrupt)?;
+        let data = match method {
+            ZIP_METHOD_STORE => raw.to_vec(),
+            ZIP_METHOD_DEFLATE => {
+                let mut data = Vec::new();
+                DeflateDecoder::new(raw).read_to_end(&mut data)
+                    .map_err(|e| format!("{}: {}", name, e))?;
+                data
+            }
+            other => return Err(format!("{}: unsupported compression method {}", name, other)),
+        };
+        if crc32fast::hash(&data) != crc {
+            return Err(format!("{}: checksum mismatch", name));
+        }
+
+        entries.push(ZipEntry { name, data, modified: None });
+    }
+    Ok(entries)
+}
+
+// Show a file in the platform's file manager. Explorer and Finder highlight the
+// file itself; elsewhere its folder is opened.
+fn reveal_in_file_manager(path: &Path) -> Result<(), String> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("explorer");
+        command.arg(format!("/select,{}", path.display()));
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg("-R").arg(path);
+        command
+    } else {
+        let folder = path.parent().ok_or("File has no parent folder")?;
+        let mut command = Command::new("xdg-open");
+        command.arg(folder);
+        command
+    };
+
+    let program = command.get_program().to_string_lossy().to_string();
+    let mut child = command.spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    // Reap the opener once it exits so it doesn't linger as a zombie
+    thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+// Whether a process name looks like the EVE client (exefile.exe) or its launcher
+fn is_eve_process(name: &str) -> bool {
+    // Wine shows Windows paths, so either separator may come before the name
+    let name = name.rsplit(['/', '\\']).next().unwrap_or(name).trim().to_lowercase();
+    name == "exefile.exe" || (name.starts_with("eve") && name.ends_with(".exe"))
+}
+
+// Best-effort check for a running EVE client. Processes under Wine don't always
+// show up with their Windows name, so a false answer proves nothing.
+fn eve_client_running() -> bool {
+    if cfg!(target_os = "linux") {
+        let Ok(entries) = fs::read_dir("/proc") else { return false };
+        entries.flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().chars().all(|c| c.is_ascii_digit()))
+            .filter_map(|entry| fs::read(entry.path().join("cmdline")).ok())
+            .any(|cmdline| cmdline.split(|&b| b == 0)
+                .next()
+                .map(|program| is_eve_process(&String::from_utf8_lossy(program)))
+                .unwrap_or(false))
+    } else {
+        let output = if cfg!(target_os = "windows") {
+            Command::new("tasklist").args(["/FO", "CSV", "/NH"]).output()
+        } else {
+            Command::new("ps").args(["-axo", "comm"]).output()
+        };
+        match output {
+            // tasklist quotes each field; the image name comes first
+            Ok(output) => String::from_utf8_lossy(&output.stdout).lines()
+                .filter_map(|line| line.split(',').next())
+                .any(|name| is_eve_process(name.trim_matches('"'))),
+            Err(_) => false,
+        }
+    }
+}
+
+fn fetch_portrait(character_id: &str) -> Result<Vec<u8>, String> {
+    let url = format!("https://images.evetech.net/characters/{}/portrait?size=32", character_id);
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("Client error: {}", e))?;
+
+    let response = client.get(&url).send().map_err(|e| format!("Request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    response.bytes()
+        .map(|bytes| bytes.to_vec())
+        .map_err(|e| format!("Read error: {}", e))
+}
+
+fn decode_portrait(bytes: &[u8]) -> Result<egui::ColorImage, String> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| format!("Decode error: {}", e))?
+        .to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Ok(egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw()))
+}
+
+fn start_portrait_lookups(
+    character_ids: Vec<String>,
+    token: LookupToken,
+    sender: Sender<ApiMessage>,
+) {
+    thread::spawn(move || {
+        for (i, char_id) in character_ids.iter().enumerate() {
+            if token.is_cancelled() {
+                return;
+            }
+
+            // The image server is separate from ESI, so a short pause is enough
+            if i > 0 {
+                thread::sleep(Duration::from_millis(100));
+            }
+
+            token.count_request();
+            let sent = sender.send(ApiMessage::Portrait {
+                character_id: char_id.clone(),
+                image: fetch_portrait(char_id),
+            });
+            // Nobody is listening any more
+            if sent.is_err() {
+                return;
+            }
+        }
+    });
+}
+
+// ESI lookups run this many requests at once unless configured otherwise
+const DEFAULT_CONCURRENT_REQUESTS: usize = 4;
+const CONCURRENT_REQUESTS_RANGE: RangeInclusive<usize> = 1..=10;
+// Seconds an ESI request may take before it counts as failed
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 10;
+const REQUEST_TIMEOUT_RANGE: RangeInclusive<u64> = 2..=30;
+// Milliseconds each request slot waits before being reused, to be polite to the API
+const DEFAULT_REQUEST_DELAY_MS: u64 = 500;
+const REQUEST_DELAY_RANGE: RangeInclusive<u64> = 0..=2000;
+
+// Back off once ESI reports this many (or fewer) errors left in the current window
+const ERROR_LIMIT_LOW_WATER: u32 = 10;
+// Times a rate-limited (HTTP 420) request is retried after waiting out the window
+const RATE_LIMIT_RETRIES: u32 = 3;
+// Times a request that couldn't connect or timed out is retried, waiting
+// TRANSIENT_RETRY_DELAY and then twice as long again before each further attempt
+const TRANSIENT_RETRIES: u32 = 3;
+const TRANSIENT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+enum EsiError {
+    NotFound(String),
+    RateLimited,
+    // Connection failures and timeouts, which are worth retrying
+    Transient(String),
+    Failed(String),
+    Cancelled,
+}
+
+impl fmt::Display for EsiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EsiError::NotFound(message) => write!(f, "{}", message),
+            EsiError::RateLimited => write!(f, "Rate limited by ESI"),
+            EsiError::Transient(message) | EsiError::Failed(message) => write!(f, "{}", message),
+            EsiError::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+// Identifies one scan's lookups. They stop once cancelled, or once a newer scan
+// bumps the shared generation counter past theirs.
+#[derive(Clone)]
+struct LookupToken {
+    cancel: Arc<AtomicBool>,
+    generation: u64,
+    current_generation: Arc<AtomicU64>,
+    // Requests sent this session, shared by every lookup
+    requests: Arc<AtomicU64>,
+}
+
+impl LookupToken {
+    fn count_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed) || self.is_superseded()
+    }
+
+    // A newer scan has started, whether or not this one was cancelled
+    fn is_superseded(&self) -> bool {
+        self.current_generation.load(Ordering::Relaxed) != self.generation
+    }
+}
+
+// Sleep in short steps so a cancelled or superseded lookup exits promptly
+async fn cancellable_sleep(duration: Duration, token: &LookupToken) {
+    let deadline = tokio::time::Instant::now() + duration;
+    while !token.is_cancelled() {
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            break;
+        }
+        tokio::time::sleep((deadline - now).min(Duration::from_millis(100))).await;
+    }
+}
+
+// Shared ESI error-limit state, so every concurrent request backs off together
+#[derive(Default)]
+struct ErrorLimitGate {
+    resume_at: Mutex<Option<tokio::time::Instant>>,
+}
+
+impl ErrorLimitGate {
+    // Wait out any active error-limit window before issuing a request
+    async fn wait(&self, token: &LookupToken) {
+        let resume_at = *self.resume_at.lock().unwrap();
+        if let Some(resume_at) = resume_at {
+            cancellable_sleep(resume_at.saturating_duration_since(tokio::time::Instant::now()), token).await;
+        }
+    }
+
+    fn pause_for(&self, duration: Duration) {
+        let until = tokio::time::Instant::now() + duration;
+        let mut resume_at = self.resume_at.lock().unwrap();
+        if resume_at.map(|current| current < until).unwrap_or(true) {
+            *resume_at = Some(until);
+        }
+    }
+
+    // Inspect the error-limit headers ESI sends with every response
+    fn observe(&self, headers: &reqwest::header::HeaderMap) -> Duration {
+        let header_number = |name: &str| -> Option<u64> {
+            headers.get(name)?.to_str().ok()?.trim().parse().ok()
+        };
+
+        let reset = Duration::from_secs(header_number("x-esi-error-limit-reset").unwrap_or(60));
+        if let Some(remain) = header_number("x-esi-error-limit-remain") {
+            if remain <= ERROR_LIMIT_LOW_WATER as u64 {
+                self.pause_for(reset);
+            }
+        }
+        reset
+    }
+}
+
+fn build_esi_client(timeout: Duration) -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| format!("Client error: {}", e))
+}
+
+async fn fetch_esi<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    gate: &ErrorLimitGate,
+    token: &LookupToken,
+    url: &str,
+    not_found: &str,
+) -> Result<(T, Option<u64>), EsiError> {
+    token.count_request();
+    match client.get(url).send().await {
+        Ok(response) => {
+            let reset = gate.observe(response.headers());
+
+            if response.status().is_success() {
+                // When ESI's cached copy of this answer runs out
+                let expires = response.headers().get(reqwest::header::EXPIRES)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(parse_http_date);
+                response.json::<T>().await
+                    .map(|data| (data, expires))
+                    .map_err(|e| EsiError::Failed(format!("Parse error: {}", e)))
+            } else if response.status().as_u16() == 404 {
+                Err(EsiError::NotFound(not_found.to_string()))
+            } else if response.status().as_u16() == 420 {
+                gate.pause_for(reset);
+                Err(EsiError::RateLimited)
+            } else {
+                Err(EsiError::Failed(format!("HTTP {}", response.status())))
+            }
+        }
+        Err(e) if e.is_connect() || e.is_timeout() => Err(EsiError::Transient(format!("Request failed: {}", e))),
+        Err(e) => Err(EsiError::Failed(format!("Request failed: {}", e))),
+    }
+}
+
+// Like fetch_esi, but waits out error-limit windows and retries rate-limited requests,
+// and retries network failures with exponential backoff. A 404 fails straight away.
+// `on_rate_limited` is called before each rate-limit retry so callers can report it.
+async fn fetch_esi_with_backoff<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    gate: &ErrorLimitGate,
+    token: &LookupToken,
+    url: &str,
+    not_found: &str,
+    mut on_rate_limited: impl FnMut(),
+) -> Result<(T, Option<u64>), EsiError> {
+    let mut attempts = 0;
+    let mut transient_attempts = 0;
+    loop {
+        gate.wait(token).await;
+        if token.is_cancelled() {
+            return Err(EsiError::Cancelled);
+        }
+        match fetch_esi(client, gate, token, url, not_found).await {
+            Err(EsiError::RateLimited) if attempts < RATE_LIMIT_RETRIES => {
+                attempts += 1;
+                on_rate_limited();
+            }
+            Err(EsiError::Transient(_)) if transient_attempts < TRANSIENT_RETRIES => {
+                cancellable_sleep(TRANSIENT_RETRY_DELAY * 2u32.pow(transient_attempts), token).await;
+                transient_attempts += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+// ESI accepts at most this many IDs per /universe/names/ request
+const BULK_NAMES_LIMIT: usize = 1000;
+
+// Resolve many character names in as few requests as possible, each with when
+// ESI's copy runs out. IDs that are missing from the result were rejected and
+// need a per-ID lookup.
+async fn fetch_names_bulk(
+    client: &reqwest::Client,
+    gate: &ErrorLimitGate,
+    token: &LookupToken,
+    esi: &EsiEndpoint,
+    character_ids: &[String],
+) -> HashMap<String, (String, Option<u64>)> {
+    let url = esi.url("universe/names/");
+    let numeric_ids: Vec<i64> = character_ids.iter()
+        .filter_map(|id| id.parse().ok())
+        .collect();
+
+    let mut names = HashMap::new();
+    for chunk in numeric_ids.chunks(BULK_NAMES_LIMIT) {
+        gate.wait(token).await;
+        if token.is_cancelled() {
+            break;
+        }
+
+        // ESI rejects the whole batch if any ID is invalid; those chunks fall back entirely
+        token.count_request();
+        let response = match client.post(&url).json(chunk).send().await {
+            Ok(response) => response,
+            Err(_) => continue,
+        };
+        let reset = gate.observe(response.headers());
+        if response.status().as_u16() == 420 {
+            gate.pause_for(reset);
+            continue;
+        }
+        if !response.status().is_success() {
+            continue;
+        }
+
+        let expires = response.headers().get(reqwest::header::EXPIRES)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_http_date);
+        if let Ok(entries) = response.json::<Vec<EsiNameResponse>>().await {
+            for entry in entries {
+                if entry.category == "character" {
+                    names.insert(entry.id.to_string(), (entry.name, expires));
+                }
+            }
+        }
+    }
+    names
+}
+
+// Corporations of many characters in as few requests as possible, each with when
+// ESI's copy runs out. Chunks ESI rejects are left out.
+async fn fetch_affiliations_bulk(
+    client: &reqwest::Client,
+    gate: &ErrorLimitGate,
+    token: &LookupToken,
+    esi: &EsiEndpoint,
+    character_ids: &[String],
+) -> HashMap<String, (i64, Option<u64>)> {
+    let url = esi.url("characters/affiliation/");
+    let numeric_ids: Vec<i64> = character_ids.iter()
+        .filter_map(|id| id.parse().ok())
+        .collect();
+
+    let mut affiliations = HashMap::new();
+    for chunk in numeric_ids.chunks(BULK_NAMES_LIMIT) {
+        gate.wait(token).await;
+        if token.is_cancelled() {
+            break;
+        }
+
+        token.count_request();
+        let response = match client.post(&url).json(chunk).send().await {
+            Ok(response) => response,
+            Err(_) => continue,
+        };
+        let reset = gate.observe(response.headers());
+        if response.status().as_u16() == 420 {
+            gate.pause_for(reset);
+            continue;
+        }
+        if !response.status().is_success() {
+            continue;
+        }
+
+        let expires = response.headers().get(reqwest::header::EXPIRES)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_http_date);
+        if let Ok(entries) = response.json::<Vec<EsiAffiliationResponse>>().await {
+            for entry in entries {
+                affiliations.insert(entry.character_id.to_string(), (entry.corporation_id, expires));
+            }
+        }
+    }
+    affiliations
+}
+
+async fn fetch_character_name(
+    client: &reqwest::Client,
+    gate: &ErrorLimitGate,
+    token: &LookupToken,
+    esi: &EsiEndpoint,
+    character_id: &str,
+    on_rate_limited: impl FnMut(),
+) -> Result<(EsiCharacterResponse, Option<u64>), EsiError> {
+    let url = esi.url(&format!("characters/{}/", character_id));
+    fetch_esi_with_backoff(client, gate, token, &url, "Character not found", on_rate_limited).await
+}
+
+async fn fetch_corporation_name(
+    client: &reqwest::Client,
+    gate: &ErrorLimitGate,
+    token: &LookupToken,
+    esi: &EsiEndpoint,
+    corporation_id: i64,
+) -> (CorporationStatus, Option<i64>) {
+    let url = esi.url(&format!("corporations/{}/", corporation_id));
+    let lookup = fetch_esi_with_backoff::<EsiCorporationResponse>(
+        client, gate, token, &url, "Corporation not found", || {},
+    ).await;
+    match lookup {
+        Ok((data, _)) => (CorporationStatus::Found(data.name), data.alliance_id),
+        Err(EsiError::Cancelled) => (CorporationStatus::Cancelled, None),
+        Err(e) => (CorporationStatus::Error(e.to_string()), None),
+    }
+}
+
+async fn fetch_alliance_name(
+    client: &reqwest::Client,
+    gate: &ErrorLimitGate,
+    token: &LookupToken,
+    esi: &EsiEndpoint,
+    alliance_id: i64,
+) -> AllianceStatus {
+    let url = esi.url(&format!("alliances/{}/", alliance_id));
+    let lookup = fetch_esi_with_backoff::<EsiAllianceResponse>(
+        client, gate, token, &url, "Alliance not found", || {},
+    ).await;
+    match lookup {
+        Ok((data, _)) => AllianceStatus::Found(data.name),
+        Err(EsiError::Cancelled) => AllianceStatus::Cancelled,
+        Err(e) => AllianceStatus::Error(e.to_string()),
+    }
+}
+
+// Corporations and alliances resolved by earlier scans, which aren't fetched again
+#[derive(Debug, Clone, Default)]
+struct KnownOrganisations {
+    // Each known corporation with its alliance, if it has one
+    corporations: HashMap<i64, Option<i64>>,
+    alliances: HashSet<i64>,
+}
+
+// User-adjustable parameters for one scan's ESI lookups
+#[derive(Debug, Clone)]
+struct LookupSettings {
+    esi: EsiEndpoint,
+    max_concurrent_requests: usize,
+    request_timeout: Duration,
+    request_delay: Duration,
+    // Look each character up on its own, for birthday and security status too,
+    // instead of by bulk name and affiliation
+    per_character: bool,
+}
+
+// Check once per scan whether the game server is up, so unresolved names can be explained
+fn start_server_status_lookup(settings: LookupSettings, token: LookupToken, sender: Sender<ApiMessage>) {
+    thread::spawn(move || {
+        let url = settings.esi.url("status/");
+        let status = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Runtime error: {}", e))
+            .and_then(|runtime| {
+                let client = build_esi_client(settings.request_timeout)?;
+                let gate = ErrorLimitGate::default();
+                runtime.block_on(fetch_esi::<EsiStatusResponse>(&client, &gate, &token, &url, "Status not found"))
+                    .map_err(|e| e.to_string())
+            });
+
+        // Still worth showing after a cancel, but not once another scan has asked again
+        if token.is_superseded() {
+            return;
+        }
+        let _ = sender.send(ApiMessage::ServerStatus(match status {
+            Ok((data, _)) => ServerStatus::Online { players: data.players },
+            Err(e) => ServerStatus::Offline(e),
+        }));
+    });
+}
+
+fn start_api_lookups(
+    character_ids: Vec<String>,
+    cached_names: HashMap<String, CachedName>,
+    not_found: HashSet<String>,
+    known: KnownOrganisations,
+    settings: LookupSettings,
+    token: LookupToken,
+    sender: Sender<ApiMessage>,
+) {
+    thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build();
+
+        match runtime {
+            Ok(runtime) => {
+                runtime.block_on(run_api_lookups(character_ids, cached_names, not_found, known, settings, token, sender));
+            }
+            Err(e) => {
+                for char_id in character_ids {
+                    let _ = sender.send(ApiMessage::Result {
+                        character_id: char_id,
+                        name: CharacterNameStatus::Error(format!("Runtime error: {}", e)),
+                        details: CharacterDetails::default(),
+                    });
+                }
+            }
+        }
+    });
+}
+
+async fn run_api_lookups(
+    character_ids: Vec<String>,
+    cached_names: HashMap<String, CachedName>,
+    not_found: HashSet<String>,
+    known: KnownOrganisations,
+    settings: LookupSettings,
+    token: LookupToken,
+    sender: Sender<ApiMessage>,
+) {
+    // A newer scan may have started before this one's thread got going
+    if token.is_cancelled() {
+        return;
+    }
+
+    // Deduplicate character IDs
+    let mut unique_ids: Vec<String> = character_ids.clone();
+    unique_ids.sort();
+    unique_ids.dedup();
+
+    // Names we already know from the disk cache resolve without hitting ESI,
+    // including bulk-resolved ones still without a birthday
+    let (cached_ids, uncached_ids): (Vec<String>, Vec<String>) = unique_ids
+        .into_iter()
+        .partition(|id| cached_names.contains_key(id));
+
+    // Characters that recently came back 404 aren't asked about again
+    let (missing_ids, mut uncached_ids): (Vec<String>, Vec<String>) = uncached_ids
+        .into_iter()
+        .partition(|id| not_found.contains(id));
+    for char_id in missing_ids {
+        let _ = sender.send(ApiMessage::Result {
+            character_id: char_id,
+            name: CharacterNameStatus::Deleted,
+            details: CharacterDetails::default(),
+        });
+    }
+
+    // Corporations seen on characters, resolved after names
+    let mut corporation_ids: Vec<i64> = Vec::new();
+
+    for char_id in &cached_ids {
+        let entry = &cached_names[char_id];
+        if let Some(corp_id) = entry.details.corporation_id {
+            corporation_ids.push(corp_id);
+        }
+        let _ = sender.send(ApiMessage::Result {
+            character_id: char_id.clone(),
+            name: CharacterNameStatus::Found(entry.name.clone()),
+            details: entry.details.clone(),
+        });
+    }
+    // Expired names stay shown from the cache while they're looked up again, and
+    // keep showing if that lookup fails
+    let now = unix_now();
+    uncached_ids.extend(cached_ids.iter().filter(|id| name_expired(&cached_names[*id], now)).cloned());
+
+    let client = match build_esi_client(settings.request_timeout) {
+        Ok(client) => client,
+        Err(e) => {
+            for char_id in uncached_ids {
+                let _ = sender.send(ApiMessage::Result {
+                    character_id: char_id,
+                    name: CharacterNameStatus::Error(e.clone()),
+                    details: CharacterDetails::default(),
+                });
+            }
+            return;
+        }
+    };
+    let limiter = Arc::new(Semaphore::new(settings.max_concurrent_requests));
+    let gate = Arc::new(ErrorLimitGate::default());
+
+    // Names and corporations in bulk, a request or two however many characters
+    // there are. Birthday and security status only come from per-character
+    // lookups, which are made when a row is expanded or a lookup is retried.
+    let bulk_names = if settings.per_character {
+        HashMap::new()
+    } else {
+        fetch_names_bulk(&client, &gate, &token, &settings.esi, &uncached_ids).await
+    };
+    let bulk_ids: Vec<String> = bulk_names.keys().cloned().collect();
+    let affiliations = fetch_affiliations_bulk(&client, &gate, &token, &settings.esi, &bulk_ids).await;
+    if token.is_cancelled() {
+        return;
+    }
+    for (char_id, (name, name_expires)) in &bulk_names {
+        let (corporation_id, expires_at) = match affiliations.get(char_id) {
+            Some(&(corporation_id, affiliation_expires)) => (Some(corporation_id), affiliation_expires),
+            None => (None, None),
+        };
+        // Stale as soon as either answer is
+        let expires_at = match (*name_expires, expires_at) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        corporation_ids.extend(corporation_id);
+        // A refreshed name keeps the birthday and security status a full lookup found
+        let earlier = cached_names.get(char_id).map(|entry| entry.details.clone()).unwrap_or_default();
+        let _ = sender.send(ApiMessage::Result {
+            character_id: char_id.clone(),
+            name: CharacterNameStatus::Found(name.clone()),
+            details: CharacterDetails { corporation_id, expires_at, ..earlier },
+        });
+    }
+
+    // One request each for the IDs the bulk endpoint rejected
+    let mut name_tasks = JoinSet::new();
+    for char_id in uncached_ids.into_iter().filter(|id| !bulk_names.contains_key(id)) {
+        let esi = settings.esi.clone();
+        let client = client.clone();
+        let limiter = limiter.clone();
+        let gate = gate.clone();
+        let token = token.clone();
+        let sender = sender.clone();
+
+        name_tasks.spawn(async move {
+            let _permit = limiter.acquire_owned().await;
+            if token.is_cancelled() {
+                return None;
+            }
+
+            let on_rate_limited = || {
+                let _ = sender.send(ApiMessage::Result {
+                    character_id: char_id.clone(),
+                    name: CharacterNameStatus::Retrying,
+                    details: CharacterDetails::default(),
+                });
+            };
+            let lookup = fetch_character_name(&client, &gate, &token, &esi, &char_id, on_rate_limited).await;
+            if token.is_cancelled() {
+                return None;
+            }
+            let (name_status, details) = match lookup {
+                Ok((data, expires_at)) => (CharacterNameStatus::Found(data.name), CharacterDetails {
+                    corporation_id: Some(data.corporation_id),
+                    birthday: data.birthday,
+                    security_status: data.security_status,
+                    expires_at,
+                }),
+                // Recorded in the not-found cache, so it isn't asked about on every scan
+                Err(EsiError::NotFound(_)) => (CharacterNameStatus::Deleted, CharacterDetails::default()),
+                Err(e) => (CharacterNameStatus::Error(e.to_string()), CharacterDetails::default()),
+            };
+            let corporation_id = details.corporation_id;
+            let _ = sender.send(ApiMessage::Result {
+                character_id: char_id,
+                name: name_status,
+                details,
+            });
+
+            cancellable_sleep(settings.request_delay, &token).await;
+            corporation_id
+        });
+    }
+    while let Some(result) = name_tasks.join_next().await {
+        if let Ok(Some(corp_id)) = result {
+            corporation_ids.push(corp_id);
+        }
+    }
+
+    // Each distinct corporation costs a single request, however many alts share it
+    let mut requested: HashSet<i64> = known.corporations.keys().copied().collect();
+    let mut corp_tasks = JoinSet::new();
+    for corp_id in corporation_ids {
+        if !requested.insert(corp_id) {
+            continue;
+        }
+
+        let esi = settings.esi.clone();
+        let client = client.clone();
+        let limiter = limiter.clone();
+        let gate = gate.clone();
+        let token = token.clone();
+        let sender = sender.clone();
+
+        corp_tasks.spawn(async move {
+            let _permit = limiter.acquire_owned().await;
+            if token.is_cancelled() {
+                return None;
+            }
+
+            let (name, alliance_id) = fetch_corporation_name(&client, &gate, &token, &esi, corp_id).await;
+            if token.is_cancelled() {
+                return None;
+            }
+            let _ = sender.send(ApiMessage::CorporationResult {
+                corporation_id: corp_id,
+                name,
+                alliance_id,
+            });
+
+            cancellable_sleep(settings.request_delay, &token).await;
+            alliance_id
+        });
+    }
+
+    // Alliances of already-known corporations may still need resolving
+    let mut alliance_ids: Vec<i64> = known.corporations.values().flatten().copied().collect();
+    while let Some(result) = corp_tasks.join_next().await {
+        if let Ok(Some(alliance_id)) = result {
+            alliance_ids.push(alliance_id);
+        }
+    }
+
+    // Likewise one request per alliance, however many corporations share it
+    let mut requested: HashSet<i64> = known.alliances;
+    let mut alliance_tasks = JoinSet::new();
+    for alliance_id in alliance_ids {
+        if !requested.insert(alliance_id) {
+            continue;
+        }
+
+        let esi = settings.esi.clone();
+        let client = client.clone();
+        let limiter = limiter.clone();
+        let gate = gate.clone();
+        let token = token.clone();
+        let sender = sender.clone();
+
+        alliance_tasks.spawn(async move {
+            let _permit = limiter.acquire_owned().await;
+            if token.is_cancelled() {
+                return;
+            }
+
+            let name = fetch_alliance_name(&client, &gate, &token, &esi, alliance_id).await;
+            if token.is_cancelled() {
+                return;
+            }
+            let _ = sender.send(ApiMessage::AllianceResult { alliance_id, name });
+
+            cancellable_sleep(settings.request_delay, &token).await;
+        });
+    }
+    while alliance_tasks.join_next().await.is_some() {}
+}
+
+impl EveSettingsApp {
+    // Read the names file again, so edits to it show up on the next scan
+    fn load_roster(&mut self) {
+        let path = self.config.names_file.trim();
+        let loaded = if path.is_empty() { Ok(HashMap::new()) } else { load_names_file(Path::new(path)) };
+        match loaded {
+            Ok(names) => {
+                self.roster_names = names;
+                self.roster_error = None;
+            }
+            Err(e) => {
+                self.roster_names.clear();
+                self.roster_error = Some(e);
+            }
+        }
+    }
+
+    // Start scanning in the background; any scan still running is cancelled.
+    // The list is updated by finish_scan once the results arrive.
+    fn scan_files(&mut self) {
+        self.scan_complete = true;
+        if self.eve_path.trim().is_empty() && self.config.additional_paths.is_empty() {
+            self.scan_job = None;
+            self.finish_scan(Err("No settings path set - enter one above or click Browse…".to_string()));
+            return;
+        }
+
+        let mut roots = vec![self.eve_path.clone()];
+        roots.extend(self.config.additional_paths.iter().cloned());
+        self.scan_job = Some(ScanJob::start(roots, self.config.scan_depth()));
+    }
+
+    // Pick up the results of the background scan when it's done
+    fn poll_scan(&mut self) {
+        let Some(job) = &self.scan_job else {
+            return;
+        };
+        let result = match job.receiver.try_recv() {
+            Ok(result) => result,
+            Err(std::sync::mpsc::TryRecvError::Empty) => return,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => Err("The scan stopped unexpectedly".to_string()),
+        };
+        self.scan_job = None;
+        self.finish_scan(result);
+    }
+
+    fn finish_scan(&mut self, result: Result<InstallScan, String>) {
+        match result {
+            Ok(InstallScan { files, errors: install_errors, warnings, primary_ok, roots }) => {
+                self.settings_files = files;
+                self.scanned_installs = roots;
+                // Files that have gone since they were selected drop out of the selection
+                if self.copy_from.as_ref().is_some_and(|path| self.file_at(path).is_none()) {
+                    self.copy_from = None;
+                }
+                let paths: HashSet<PathBuf> = self.settings_files.iter().map(|f| f.path.clone()).collect();
+                self.copy_to.retain(|path| paths.contains(path));
+                self.scan_warnings = warnings;
+                self.sort_files();
+                self.active_files = active_profile_files(&self.settings_files);
+                self.refresh_file_details();
+                // Some installs may have failed while others scanned fine
+                self.error_message = if install_errors.is_empty() {
+                    None
+                } else {
+                    Some(install_errors.join("; "))
+                };
+
+                // Remember the path once it has scanned successfully
+                if primary_ok && self.config.eve_path.as_deref() != Some(self.eve_path.as_str()) {
+                    self.config.eve_path = Some(self.eve_path.clone());
+                    self.save_config();
+                }
+
+                // Names from the names file are final, so those characters never reach ESI
+                self.load_roster();
+                for file in &mut self.settings_files {
+                    if let Some(name) = self.roster_names.get(&file.character_id) {
+                        file.character_name = CharacterNameStatus::Found(name.clone());
+                        self.character_names.insert(file.character_id.clone(), file.character_name.clone());
+                    }
+                }
+
+                // Portraits come from the image server, so every character gets one
+                let portrait_ids: Vec<String> = self.settings_files
+                    .iter()
+                    .map(|f| f.character_id.clone())
+                    .collect();
+
+                // Collect unique character IDs for API lookups
+                let char_ids: Vec<String> = portrait_ids.iter()
+                    .filter(|id| !self.roster_names.contains_key(*id))
+                    .cloned()
+                    .collect();
+
+                // Stop whatever the previous scan still had in flight
+                self.lookup_cancel.store(true, Ordering::Relaxed);
+                self.lookup_cancel = Arc::new(AtomicBool::new(false));
+                self.scan_generation.fetch_add(1, Ordering::Relaxed);
+
+                // Nothing goes over the network; copying works the same either way
+                if self.config.offline {
+                    for id in &char_ids {
+                        self.character_names.insert(id.clone(), CharacterNameStatus::Offline);
+                    }
+                    for file in &mut self.settings_files {
+                        if !self.roster_names.contains_key(&file.character_id) {
+                            file.character_name = CharacterNameStatus::Offline;
+                        }
+                    }
+                    self.server_status = None;
+                    self.api_receiver = None;
+                    self.api_sender = None;
+                    return;
+                }
+
+                // Initialize all as loading
+                for id in &char_ids {
+                    self.character_names.insert(id.clone(), CharacterNameStatus::Loading);
+                }
+
+                // Corporations and alliances resolved in an earlier scan don't need fetching again
+                self.corporation_names.retain(|_, status| matches!(status, CorporationStatus::Found(_)));
+                self.alliance_names.retain(|_, status| matches!(status, AllianceStatus::Found(_)));
+                let known = self.known_organisations();
+
+                // Start background API lookups
+                let (sender, receiver) = channel();
+                self.api_receiver = Some(receiver);
+                self.api_sender = Some(sender.clone());
+
+                // Portraits we already decoded this session are kept; failures get another go
+                self.portraits.retain(|_, status| matches!(status, PortraitStatus::Loaded(_)));
+                let mut missing_portraits: Vec<String> = portrait_ids.iter()
+                    .filter(|id| !self.portraits.contains_key(*id))
+                    .cloned()
+                    .collect();
+                missing_portraits.sort();
+                missing_portraits.dedup();
+                start_portrait_lookups(missing_portraits, self.lookup_token(), sender.clone());
+
+                self.server_status = None;
+                start_server_status_lookup(self.config.lookup_settings(), self.lookup_token(), sender.clone());
+
+                start_api_lookups(
+                    char_ids,
+                    self.name_cache.clone(),
+                    recent_not_found(&self.not_found_cache),
+                    known,
+                    self.config.lookup_settings(),
+                    self.lookup_token(),
+                    sender,
+                );
+            }
+            Err(e) => {
+                self.error_message = Some(e);
+                self.scan_warnings.clear();
+            }
+        }
+    }
+
+    // Logging is best-effort and must never hold up the UI
+    fn log_operation(&self, entry: String) {
+        self.writer.log(entry);
+    }
+
+    fn open_operation_log(&mut self) {
+        let result = match operation_log_path() {
+            Some(path) if path.exists() => reveal_in_file_manager(&path),
+            Some(_) => Err("Nothing has been logged yet".to_string()),
+            None => Err("No config directory available".to_string()),
+        };
+        if let Err(e) = result {
+            self.popup_title = "Operation Log".to_string();
+            self.popup_message = e;
+            self.popup_success = false;
+            self.show_popup = true;
+        }
+    }
+
+    // Write the config off the UI thread
+    fn save_config(&self) {
+        self.writer.save("config.json", &self.config);
+    }
+
+    // Character IDs point at different characters on each server, so nothing
+    // resolved against the old datasource can be trusted after switching
+    fn set_datasource(&mut self, datasource: Datasource) {
+        self.config.datasource = datasource;
+        self.save_config();
+
+        self.name_cache.clear();
+        self.writer.save(NAME_CACHE_FILE, &self.name_cache);
+        self.not_found_cache.clear();
+        self.writer.save(NOT_FOUND_CACHE_FILE, &self.not_found_cache);
+        self.character_names.clear();
+        self.corporation_names.clear();
+        self.corporation_alliances.clear();
+        self.alliance_names.clear();
+        self.scan_complete = false;
+    }
+
+    fn toggle_sort(&mut self, key: SortKey) {
+        if self.sort_key == key {
+            self.sort_ascending = !self.sort_ascending;
+        } else {
+            self.sort_key = key;
+            self.sort_ascending = true;
+        }
+        self.sort_files();
+    }
+
+    // Rows are only ever reordered here, and the selection (copy_from, copy_to) and
+    // expanded rows are keyed by character ID and path, so re-sorting never moves them
+    fn sort_files(&mut self) {
+        let key = self.sort_key;
+        let ascending = self.sort_ascending;
+        let pinned = &self.config.pinned;
+
+        self.settings_files.sort_by(|a, b| {
+            // Pinned characters come first in either direction
+            let pins = pinned.contains(&b.character_id).cmp(&pinned.contains(&a.character_id));
+            let ordering = match key {
+                SortKey::Filename => {
+                    let ordering = a.filename.cmp(&b.filename);
+                    if ascending { ordering } else { ordering.reverse() }
+                }
+                SortKey::CharacterId => {
+                    let ordering = compare_character_ids(&a.character_id, &b.character_id);
+                    if ascending { ordering } else { ordering.reverse() }
+                }
+                SortKey::CharacterName => {
+                    // Unresolved rows always sink to the bottom, whichever the direction
+                    let rank = |status: &CharacterNameStatus| match status {
+                        CharacterNameStatus::Found(_) => 0,
+                        CharacterNameStatus::Loading | CharacterNameStatus::Retrying => 1,
+                        CharacterNameStatus::Cancelled => 2,
+                        CharacterNameStatus::Offline => 3,
+                        CharacterNameStatus::Error(_) => 4,
+                        CharacterNameStatus::Deleted => 5,
+                    };
+                    match (&a.character_name, &b.character_name) {
+                        (CharacterNameStatus::Found(name_a), CharacterNameStatus::Found(name_b)) => {
+                            let ordering = name_a.to_lowercase().cmp(&name_b.to_lowercase());
+                            if ascending { ordering } else { ordering.reverse() }
+                        }
+                        (status_a, status_b) => rank(status_a).cmp(&rank(status_b)),
+                    }
+                }
+            };
+            // A character can have a file in several profiles; the path settles those, so
+            // the order depends only on the files and never on the order names arrived in
+            pins
+                .then(ordering)
+                .then_with(|| compare_character_ids(&a.character_id, &b.character_id))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+    }
+
+    fn process_api_messages(&mut self, ctx: &egui::Context) {
+        let mut cache_dirty = false;
+        let mut not_found_dirty = false;
+        let mut names_changed = false;
+
+        if let Some(receiver) = &self.api_receiver {
+            while let Ok(msg) = receiver.try_recv() {
+                match msg {
+                    ApiMessage::Result { character_id, name, details } => {
+                        if self.roster_names.contains_key(&character_id) {
+                            continue;
+                        }
+                        // A name the user typed in only gives way to a real one
+                        let user_provided = self.name_cache.get(&character_id)
+                            .is_some_and(|entry| entry.user_provided);
+                        if user_provided && !matches!(name, CharacterNameStatus::Found(_)) {
+                            continue;
+                        }
+                        // A details lookup that fails keeps the name already shown; only a 404 replaces it
+                        let shown = matches!(self.character_names.get(&character_id), Some(CharacterNameStatus::Found(_)));
+                        if shown && !matches!(name, CharacterNameStatus::Found(_) | CharacterNameStatus::Deleted) {
+                            continue;
+                        }
+                        let (names, not_found) = merge_lookup_result(
+                            &mut self.name_cache, &mut self.not_found_cache, &character_id, &name, &details);
+                        cache_dirty |= names;
+                        not_found_dirty |= not_found;
+
+                        if let Some(corp_id) = details.corporation_id {
+                            self.corporation_names.entry(corp_id).or_insert(CorporationStatus::Loading);
+                        }
+
+                        self.character_names.insert(character_id.clone(), name.clone());
+                        names_changed = true;
+                        // Update all files with this character ID
+                        for file in &mut self.settings_files {
+                            if file.character_id == character_id {
+                                file.character_name = name.clone();
+                                file.details = details.clone();
+                            }
+                        }
+                    }
+                    ApiMessage::CorporationResult { corporation_id, name, alliance_id } => {
+                        if matches!(name, CorporationStatus::Found(_)) {
+                            self.corporation_alliances.insert(corporation_id, alliance_id);
+                        }
+                        if let Some(alliance_id) = alliance_id {
+                            self.alliance_names.entry(alliance_id).or_insert(AllianceStatus::Loading);
+                        }
+                        self.corporation_names.insert(corporation_id, name);
+                    }
+                    ApiMessage::AllianceResult { alliance_id, name } => {
+                        self.alliance_names.insert(alliance_id, name);
+                    }
+                    ApiMessage::Portrait { character_id, image } => {
+                        // Decode once on arrival; repaints reuse the texture
+                        let status = match image.and_then(|bytes| decode_portrait(&bytes)) {
+                            Ok(color_image) => PortraitStatus::Loaded(ctx.load_texture(
+                                format!("portrait_{}", character_id),
+                                color_image,
+                                egui::TextureOptions::LINEAR,
+                            )),
+                            Err(_) => PortraitStatus::Failed,
+                        };
+                        self.portraits.insert(character_id, status);
+                    }
+                    ApiMessage::ServerStatus(status) => {
+                        self.server_status = Some(status);
+                    }
+                }
+            }
+        }
+
+        // Keep the chosen ordering as names arrive
+        if names_changed && self.sort_key == SortKey::CharacterName {
+            self.sort_files();
+        }
+
+        if cache_dirty {
+            // Caching is best-effort; a failed write just means a lookup next session
+            self.writer.save(NAME_CACHE_FILE, &self.name_cache);
+        }
+        if not_found_dirty {
+            self.writer.save(NOT_FOUND_CACHE_FILE, &self.not_found_cache);
+        }
+    }
+
+    fn known_organisations(&self) -> KnownOrganisations {
+        let corporations = self.corporation_names
+            .iter()
+            .filter(|(_, status)| matches!(status, CorporationStatus::Found(_)))
+            .map(|(id, _)| (*id, self.corporation_alliances.get(id).copied().flatten()))
+            .collect();
+        let alliances = self.alliance_names
+            .iter()
+            .filter(|(_, status)| matches!(status, AllianceStatus::Found(_)))
+            .map(|(id, _)| *id)
+            .collect();
+        KnownOrganisations { corporations, alliances }
+    }
+
+    // Re-queue a single character lookup on the existing channel
+    fn retry_lookup(&mut self, character_id: &str) {
+        self.lookup_again(vec![character_id.to_string()]);
+    }
+
+    // Cached names ESI said would be stale by now, which the next scan looks up
+    // again anyway. Names typed in by hand never are.
+    fn expired_name_ids(&self) -> Vec<String> {
+        let now = unix_now();
+        let mut ids: Vec<String> = self.settings_files.iter()
+            .filter(|f| matches!(f.character_name, CharacterNameStatus::Found(_)))
+            .filter(|f| self.name_cache.get(&f.character_id)
+                .is_some_and(|entry| name_expired(entry, now)))
+            .map(|f| f.character_id.clone())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    fn refresh_expired_names(&mut self) {
+        let ids = self.expired_name_ids();
+        self.lookup_again(ids);
+    }
+
+    // Look characters up on the existing channel, bypassing both caches
+    fn lookup_again(&mut self, mut character_ids: Vec<String>) {
+        let sender = match &self.api_sender {
+            Some(sender) => sender.clone(),
+            None => return,
+        };
+        character_ids.retain(|id| !self.roster_names.contains_key(id));
+
+        // A cancelled scan's flag stays tripped; retries get a fresh one
+        if self.lookup_cancel.load(Ordering::Relaxed) {
+            self.lookup_cancel = Arc::new(AtomicBool::new(false));
+        }
+
+        for character_id in &character_ids {
+            self.character_names.insert(character_id.clone(), CharacterNameStatus::Loading);
+        }
+        for file in &mut self.settings_files {
+            if character_ids.contains(&file.character_id) {
+                file.character_name = CharacterNameStatus::Loading;
+            }
+        }
+
+        // Asked for by hand, so neither cache gets a say
+        start_api_lookups(
+            character_ids,
+            HashMap::new(),
+            HashSet::new(),
+            self.known_organisations(),
+            LookupSettings { per_character: true, ..self.config.lookup_settings() },
+            self.lookup_token(),
+            sender,
+        );
+    }
+
+    // Names resolved in bulk come without birthday and security status; those are
+    // looked up once the row is expanded, keeping the name shown meanwhile
+    fn lookup_missing_details(&mut self, path: &Path) {
+        let Some(file) = self.file_at(path) else {
+            return;
+        };
+        let character_id = file.character_id.clone();
+        let wanted = matches!(file.character_name, CharacterNameStatus::Found(_))
+            && file.details.birthday.is_none()
+            && !self.config.offline
+            && !self.roster_names.contains_key(&character_id)
+            && !self.name_cache.get(&character_id).is_some_and(|entry| entry.user_provided);
+        let Some(sender) = self.api_sender.clone().filter(|_| wanted) else {
+            return;
+        };
+
+        if self.lookup_cancel.load(Ordering::Relaxed) {
+            self.lookup_cancel = Arc::new(AtomicBool::new(false));
+        }
+        start_api_lookups(
+            vec![character_id],
+            HashMap::new(),
+            HashSet::new(),
+            self.known_organisations(),
+            LookupSettings { per_character: true, ..self.config.lookup_settings() },
+            self.lookup_token(),
+            sender,
+        );
+    }
+
+    // Store a name typed in for a character, as if ESI had resolved it. An empty
+    // name drops the manual one and looks the character up again.
+    fn set_manual_name(&mut self, path: &Path, name: &str) {
+        let Some(file) = self.file_at(path) else {
+            return;
+        };
+        let character_id = file.character_id.clone();
+        let details = file.details.clone();
+        let name = name.trim();
+
+        if name.is_empty() {
+            if self.name_cache.get(&character_id).is_some_and(|entry| entry.user_provided) {
+                self.name_cache.remove(&character_id);
+                self.writer.save(NAME_CACHE_FILE, &self.name_cache);
+                let status = CharacterNameStatus::Error("No name".to_string());
+                self.character_names.insert(character_id.clone(), status.clone());
+                for file in &mut self.settings_files {
+                    if file.character_id == character_id {
+                        file.character_name = status.clone();
+                    }
+                }
+                self.retry_lookup(&character_id);
+            }
+            return;
+        }
+
+        self.name_cache.insert(character_id.clone(), CachedName {
+            name: name.to_string(),
+            details,
+            cached_at: unix_now(),
+            user_provided: true,
+        });
+        self.writer.save(NAME_CACHE_FILE, &self.name_cache);
+        let status = CharacterNameStatus::Found(name.to_string());
+        self.character_names.insert(character_id.clone(), status.clone());
+        for file in &mut self.settings_files {
+            if file.character_id == character_id {
+                file.character_name = status.clone();
+            }
+        }
+        if self.sort_key == SortKey::CharacterName {
+            self.sort_files();
+        }
+    }
+
+    // Token tying background lookups to the current scan
+    fn lookup_token(&self) -> LookupToken {
+        LookupToken {
+            cancel: self.lookup_cancel.clone(),
+            generation: self.scan_generation.load(Ordering::Relaxed),
+            current_generation: self.scan_generation.clone(),
+            requests: self.esi_requests.clone(),
+        }
+    }
+
+    // Stop outstanding lookups, keeping whatever already resolved
+    fn cancel_lookups(&mut self) {
+        self.lookup_cancel.store(true, Ordering::Relaxed);
+
+        for status in self.character_names.values_mut() {
+            if status.is_pending() {
+                *status = CharacterNameStatus::Cancelled;
+            }
+        }
+        for file in &mut self.settings_files {
+            if file.character_name.is_pending() {
+                file.character_name = CharacterNameStatus::Cancelled;
+            }
+            self.portraits.entry(file.character_id.clone()).or_insert(PortraitStatus::Failed);
+        }
+        for status in self.corporation_names.values_mut().chain(self.alliance_names.values_mut()) {
+            if matches!(status, CorporationStatus::Loading) {
+                *status = CorporationStatus::Cancelled;
+            }
+        }
+    }
+
+    fn copy_settings(&mut self) {
+        self.popup_title = "Copy Status".to_string();
+
+        let source_path = match &self.copy_from {
+            Some(path) => path.clone(),
+            None => {
+                self.popup_message = "No source selected".to_string();
+                self.popup_success = false;
+                self.show_popup = true;
+                return;
+            }
+        };
+
+        let dest_paths = self.sorted_destinations();
+
+        match copy_settings_files(&self.settings_files, &source_path, &dest_paths, self.copy_options) {
+            Ok(outcome) => {
+                let source = format!("{} ({})", self.path_display_name(&source_path), source_path.display());
+                self.log_operation(copy_log_entry(&source, &outcome));
+                self.record_history(&source_path, &dest_paths);
+                self.popup_message = outcome.headline();
+                self.popup_success = outcome.is_success();
+                // Anything that isn't a listed character file is the account file next to one
+                let identical = outcome.identical.iter()
+                    .map(|path| (path.display().to_string(), Ok(()), true));
+                self.popup_rows = outcome.file_results.into_iter()
+                    .map(|(path, result)| (path, result, false))
+                    .chain(identical)
+                    .map(|(path, result, identical)| {
+                        let name = match self.file_at(Path::new(&path)) {
+                            Some(_) => self.path_display_name(Path::new(&path)),
+                            None if dest_paths.iter().any(|dest| dest.display().to_string() == path) => {
+                                "(not found)".to_string()
+                            }
+                            None => "Account settings".to_string(),
+                        };
+                        ResultRow { name, path, result, identical }
+                    })
+                    .collect();
+            }
+            Err(e) => {
+                self.popup_message = e;
+                self.popup_success = false;
+                self.show_popup = true;
+                return;
+            }
+        }
+        self.show_popup = true;
+        self.refresh_file_details();
+
+        // Clear selections after copy
+        self.copy_from = None;
+        self.copy_to.clear();
+    }
+
+    fn record_history(&mut self, source: &Path, destinations: &[PathBuf]) {
+        let entry = HistoryEntry {
+            time: log_timestamp(),
+            user: current_user(),
+            source: source.to_path_buf(),
+            source_name: self.path_display_name(source),
+            destinations: destinations.iter()
+                .map(|path| (path.clone(), self.path_display_name(path)))
+                .collect(),
+        };
+        self.history.insert(0, entry);
+        self.history.truncate(HISTORY_MAX_ENTRIES);
+
+        self.writer.save("history.json", &self.history);
+    }
+
+    // Select the files of a past copy again; ones the current scan lacks are reported
+    fn reselect_history(&mut self, index: usize) {
+        let Some(entry) = self.history.get(index).cloned() else {
+            return;
+        };
+        if self.file_at(&entry.source).is_none() {
+            self.popup_title = "Copy History".to_string();
+            self.popup_message = format!("{} is no longer in the list:\n{}",
+                entry.source_name, entry.source.display());
+            self.popup_success = false;
+            self.show_popup = true;
+            return;
+        }
+
+        self.copy_from = Some(entry.source.clone());
+        self.copy_to.clear();
+        let mut missing = Vec::new();
+        for (path, name) in &entry.destinations {
+            if self.file_at(path).is_some() && *path != entry.source {
+                self.copy_to.insert(path.clone());
+            } else {
+                missing.push(name.clone());
+            }
+        }
+        self.show_history = false;
+
+        if !missing.is_empty() {
+            self.popup_title = "Copy History".to_string();
+            self.popup_message = format!("Selected again, except for {} destination(s) no longer in the list:\n{}",
+                missing.len(), missing.join(", "));
+            self.popup_success = false;
+            self.show_popup = true;
+        }
+    }
+
+    // Compare the source with the only selected destination
+    fn open_diff(&mut self) {
+        let (Some(source), Some(dest)) = (self.copy_from.clone(), self.copy_to.iter().next().cloned()) else {
+            return;
+        };
+        match fs::read(&source).and_then(|a| fs::read(&dest).map(|b| (a, b))) {
+            Ok((source_contents, dest_contents)) => {
+                self.settings_diff = Some(diff_settings(
+                    self.path_display_name(&source),
+                    self.path_display_name(&dest),
+                    &source_contents,
+                    &dest_contents,
+                ));
+            }
+            Err(e) => {
+                self.popup_title = "Diff".to_string();
+                self.popup_message = format!("Failed to read settings: {}", e);
+                self.popup_success = false;
+                self.show_popup = true;
+            }
+        }
+    }
+
+    // Check that exactly two characters are selected (source plus destinations) and
+    // ask before swapping them, with the same warnings a copy gets
+    fn request_swap(&mut self) {
+        self.popup_title = "Swap Status".to_string();
+
+        let mut selected: Vec<PathBuf> = self.copy_from.iter().cloned().collect();
+        selected.extend(self.copy_to.iter().cloned());
+        selected.sort();
+        selected.dedup();
+
+        if selected.len() != 2 {
+            self.popup_message = format!(
+                "Select exactly two characters to swap (currently {} selected)", selected.len());
+            self.popup_success = false;
+            self.show_popup = true;
+            return;
+        }
+
+        let (first_path, second_path) = match (self.file_at(&selected[0]), self.file_at(&selected[1])) {
+            (Some(a), Some(b)) if a.invalid || b.invalid => {
+                let broken = if a.invalid { &selected[0] } else { &selected[1] };
+                self.popup_message = format!(
+                    "{}'s file is empty or truncated, so its settings can't be swapped",
+                    self.path_display_name(broken));
+                self.popup_success = false;
+                self.show_popup = true;
+                return;
+            }
+            (Some(a), Some(b)) => (a.path.clone(), b.path.clone()),
+            _ => {
+                self.popup_message = "Settings file not found".to_string();
+                self.popup_success = false;
+                self.show_popup = true;
+                return;
+            }
+        };
+
+        self.eve_running = eve_client_running();
+        // Each side gets the other's settings, so the newer one goes back in time
+        let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+        self.copy_downgrades = match (modified(&first_path), modified(&second_path)) {
+            (Some(first), Some(second)) if first > second => vec![first_path.clone()],
+            (Some(first), Some(second)) if second > first => vec![second_path.clone()],
+            _ => Vec::new(),
+        };
+        self.downgrade_acknowledged = false;
+        // Account files stay put in a swap, so only the two character files count
+        let options = CopyOptions { include_account_settings: false, ..self.copy_options };
+        self.copy_read_only = read_only_destinations(
+            &self.settings_files, &first_path, &[first_path.clone(), second_path.clone()], options);
+        // Opt-in for each swap, as for a copy
+        self.copy_options.clear_read_only = false;
+        self.confirm_swap = Some((first_path, second_path));
+    }
+
+    // Exchange the settings of two files, once the swap is confirmed
+    fn swap_settings(&mut self, first_path: PathBuf, second_path: PathBuf) {
+        self.popup_title = "Swap Status".to_string();
+
+        let first_name = self.path_display_name(&first_path);
+        let second_name = self.path_display_name(&second_path);
+
+        match swap_settings_files(&self.settings_files, &first_path, &second_path, self.copy_options) {
+            Ok(outcome) => {
+                let header = format!("swap {} with {}", first_name, second_name);
+                self.log_operation(log_file_results(header, &outcome.file_results));
+                let mut message = if !outcome.identical.is_empty() {
+                    format!("{} and {} already have identical settings; nothing to swap", first_name, second_name)
+                } else if outcome.is_success() {
+                    format!("Swapped settings between {} and {}", first_name, second_name)
+                } else {
+                    format!("Failed to swap {} and {}: {}", first_name, second_name, outcome.errors.join(", "))
+                };
+                if !outcome.warnings.is_empty() {
+                    message.push_str(&format!("\nWarnings: {}", outcome.warnings.join(", ")));
+                }
+                message.push_str(&outcome.restore_script_note());
+                self.popup_message = message;
+                self.popup_success = outcome.is_success();
+            }
+            Err(e) => {
+                self.popup_message = e;
+                self.popup_success = false;
+            }
+        }
+        self.show_popup = true;
+        self.refresh_file_details();
+
+        // Clear selections after swap
+        self.copy_from = None;
+        self.copy_to.clear();
+    }
+
+    fn export_csv(&mut self) {
+        self.open_file_dialog(|| pick_save_file(FileDialogPurpose::ExportCsv, "toonsettings.csv"));
+    }
+
+    fn export_csv_to(&mut self, path: PathBuf) {
+        self.popup_title = "Export CSV".to_string();
+
+        match fs::write(&path, build_csv(&self.settings_files)) {
+            Ok(_) => {
+                self.popup_message = format!("Exported {} character(s) to {}",
+                    self.settings_files.len(), path.display());
+                self.popup_success = true;
+            }
+            Err(e) => {
+                self.popup_message = format!("Failed to write {}: {}", path.display(), e);
+                self.popup_success = false;
+            }
+        }
+        self.show_popup = true;
+    }
+
+    // Where a file goes inside a Backup All zip: its path below the install it was
+    // found in, with files from additional installs kept in their own folder. The
+    // installs are the ones the last scan used, whatever the path box says now.
+    fn backup_entry_name(&self, path: &Path, install: &Path) -> Option<String> {
+        let relative = path.strip_prefix(install).ok()?;
+        let mut name: Vec<String> = relative.components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        let index = self.scanned_installs.iter().position(|root| root == install)?;
+        if index > 0 {
+            name.insert(0, format!("additional_{}", index));
+        }
+        Some(name.join("/"))
+    }
+
+    // Snapshot every discovered character file, plus the account files beside
+    // them, into a single zip
+    fn backup_all(&mut self) {
+        let default_name = format!("toonsettings-backup-{}.zip", backup_timestamp());
+        self.open_file_dialog(|| pick_save_file(FileDialogPurpose::BackupAll, &default_name));
+    }
+
+    fn backup_all_to(&mut self, zip_path: PathBuf) {
+        self.popup_title = "Backup All".to_string();
+
+        // Character files, then every core_user_*.dat in the same profile folders
+        let mut sources: Vec<(PathBuf, PathBuf)> = self.settings_files.iter()
+            .map(|f| (f.path.clone(), f.install.clone()))
+            .collect();
+        let mut folders: Vec<(PathBuf, PathBuf)> = self.settings_files.iter()
+            .filter_map(|f| f.path.parent().map(|folder| (folder.to_path_buf(), f.install.clone())))
+            .collect();
+        folders.sort();
+        folders.dedup();
+        for (folder, install) in folders {
+            let Ok(entries) = fs::read_dir(&folder) else { continue };
+            for entry in entries.flatten() {
+                let filename = entry.file_name().to_string_lossy().to_string();
+                if filename.starts_with("core_user_") && filename.ends_with(".dat") {
+                    sources.push((entry.path(), install.clone()));
+                }
+            }
+        }
+
+        let mut entries = Vec::new();
+        let mut errors = Vec::new();
+        for (path, install) in &sources {
+            let Some(name) = self.backup_entry_name(path, install) else {
+                errors.push(format!("{}: outside its install", path.display()));
+                continue;
+            };
+            match fs::read(path) {
+                Ok(data) => entries.push(ZipEntry {
+                    name,
+                    data,
+                    modified: fs::metadata(path).and_then(|m| m.modified()).ok(),
+                }),
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+
+        match build_zip(&entries).and_then(|archive| write_atomic(&zip_path, &archive)) {
+            Ok(_) if errors.is_empty() => {
+                self.popup_message = format!("Backed up {} file(s) to {}", entries.len(), zip_path.display());
+                self.popup_success = true;
+            }
+            Ok(_) => {
+                self.popup_message = format!("Backed up {} file(s) to {}, but {} failed: {}",
+                    entries.len(), zip_path.display(), errors.len(), errors.join(", "));
+                self.popup_success = false;
+            }
+            Err(e) => {
+                self.popup_message = format!("Failed to write {}: {}", zip_path.display(), e);
+                self.popup_success = false;
+            }
+        }
+        self.show_popup = true;
+    }
+
+    // Inverse of backup_entry_name: a zip path back to a location on disk
+    fn restore_target(&self, name: &str) -> Option<PathBuf> {
+        let mut parts: Vec<&str> = name.split('/').collect();
+        if parts.iter().any(|part| part.is_empty() || *part == "." || *part == "..") {
+            return None;
+        }
+
+        let mut target = PathBuf::from(&self.eve_path);
+        if let Some(index) = parts[0].strip_prefix("additional_").and_then(|n| n.parse::<usize>().ok()) {
+            target = PathBuf::from(self.config.additional_paths.get(index.checked_sub(1)?)?);
+            parts.remove(0);
+        }
+        for part in parts {
+            target.push(part);
+        }
+        Some(target)
+    }
+
+    fn open_restore(&mut self) {
+        self.open_file_dialog(pick_zip_file);
+    }
+
+    fn restore_from(&mut self, zip_path: PathBuf) {
+        self.popup_title = "Restore".to_string();
+
+        let entries = match fs::read(&zip_path).map_err(|e| e.to_string()).and_then(|archive| read_zip(&archive)) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.popup_message = format!("Failed to read {}: {}", zip_path.display(), e);
+                self.popup_success = false;
+                self.show_popup = true;
+                return;
+            }
+        };
+
+        let char_regex = Regex::new(r"(^|/)core_char_\d+\.dat$").unwrap();
+        let mut entries: Vec<RestoreEntry> = entries.into_iter()
+            .filter(|entry| char_regex.is_match(&entry.name))
+            .map(|entry| {
+                let target = self.restore_target(&entry.name);
+                // Only preselect files whose folder is actually there
+                let selected = target.as_ref()
+                    .and_then(|t| t.parent())
+                    .map(|folder| folder.is_dir())
+                    .unwrap_or(false);
+                RestoreEntry { name: entry.name, target, data: entry.data, selected }
+            })
+            .collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        if entries.is_empty() {
+            self.popup_message = format!("No character settings files found in {}", zip_path.display());
+            self.popup_success = false;
+            self.show_popup = true;
+            return;
+        }
+
+        self.restore_plan = Some(RestorePlan { zip_path, entries, confirming: false });
+    }
+
+    // Write the selected files back, backing up whatever they replace
+    fn restore_selected(&mut self, plan: RestorePlan) {
+        self.popup_title = "Restore".to_string();
+
+        let mut restored = 0;
+        let mut errors = Vec::new();
+        for entry in plan.entries.iter().filter(|entry| entry.selected) {
+            let target = match &entry.target {
+                Some(target) => target,
+                None => {
+                    errors.push(format!("{}: no matching install", entry.name));
+                    continue;
+                }
+            };
+            // A missing folder means the zip came from a different layout; don't guess
+            if !target.parent().map(|folder| folder.is_dir()).unwrap_or(false) {
+                errors.push(format!("{}: folder does not exist", target.display()));
+                continue;
+            }
+
+            let result = if target.exists() { backup_file(target).map(|_| ()) } else { Ok(()) };
+            match result.and_then(|_| write_atomic(target, &entry.data)) {
+                Ok(_) => restored += 1,
+                Err(e) => errors.push(format!("{}: {}", target.display(), e)),
+            }
+        }
+
+        if errors.is_empty() {
+            self.popup_message = format!("Restored {} file(s) from {}", restored, plan.zip_path.display());
+            self.popup_success = true;
+        } else {
+            self.popup_message = format!("Restored {} file(s), but {} failed: {}",
+                restored, errors.len(), errors.join(", "));
+            self.popup_success = false;
+        }
+        self.show_popup = true;
+
+        // Pick up the restored files
+        self.scan_complete = false;
+    }
+
+    // Files of the rows currently shown in the list
+    fn displayed_paths(&self) -> Vec<PathBuf> {
+        self.settings_files
+            .iter()
+            .filter(|f| self.matches_filter(f))
+            .map(|f| f.path.clone())
+            .collect()
+    }
+
+    // Whether a row is shown: the unresolved toggles, then a case-insensitive
+    // search against the ID, the note and, once resolved, the name
+    fn matches_filter(&self, file: &SettingsFile) -> bool {
+        match file.character_name {
+            CharacterNameStatus::Loading | CharacterNameStatus::Retrying if self.hide_unresolved => return false,
+            CharacterNameStatus::Error(_) | CharacterNameStatus::Deleted
+                if self.hide_unresolved && self.hide_errors => return false,
+            _ => {}
+        }
+
+        let query = self.search_query.trim().to_lowercase();
+        if query.is_empty() {
+            return true;
+        }
+
+        if file.character_id.to_lowercase().contains(&query) {
+            return true;
+        }
+        if let Some(note) = self.notes.get(&file.character_id) {
+            if note.to_lowercase().contains(&query) {
+                return true;
+            }
+        }
+        match &file.character_name {
+            CharacterNameStatus::Found(name) => name.to_lowercase().contains(&query),
+            _ => false,
+        }
+    }
+
+    fn select_all_destinations(&mut self) {
+        for path in self.displayed_paths() {
+            // The source can never be a destination
+            if self.copy_from.as_ref() != Some(&path) {
+                self.copy_to.insert(path);
+            }
+        }
+    }
+
+    // Set every Copy To box between the last clicked row and `path` to `checked`.
+    // Without a usable starting row only `path` itself changes.
+    fn apply_copy_to_range(&mut self, path: &Path, checked: bool, display_order: &[usize]) {
+        let position = |target: &Path| display_order.iter()
+            .position(|&index| self.settings_files[index].path == target);
+        let end = position(path);
+        let start = self.last_toggled.as_deref().and_then(position).or(end);
+
+        let range: Vec<PathBuf> = match (start, end) {
+            (Some(start), Some(end)) => display_order[start.min(end)..=start.max(end)].iter()
+                .map(|&index| self.settings_files[index].path.clone())
+                .collect(),
+            _ => vec![path.to_path_buf()],
+        };
+        for range_path in range {
+            // The source can never be a destination
+            if self.copy_from.as_ref() == Some(&range_path) {
+                continue;
+            }
+            if checked {
+                self.copy_to.insert(range_path);
+            } else {
+                self.copy_to.remove(&range_path);
+            }
+        }
+        self.last_toggled = Some(path.to_path_buf());
+    }
+
+    fn file_at(&self, path: &Path) -> Option<&SettingsFile> {
+        self.settings_files.iter().find(|f| f.path == path)
+    }
+
+    fn source_file(&self) -> Option<&SettingsFile> {
+        self.file_at(self.copy_from.as_deref()?)
+    }
+
+    // Character ID of the source file
+    fn source_character(&self) -> Option<&str> {
+        self.source_file().map(|f| f.character_id.as_str())
+    }
+
+    // Destinations in a stable order, for copying and for dialogs
+    fn sorted_destinations(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self.copy_to.iter().cloned().collect();
+        paths.sort_by(|a, b| {
+            let id = |path: &Path| self.file_at(path).map(|f| f.character_id.clone()).unwrap_or_default();
+            compare_character_ids(&id(a), &id(b)).then_with(|| a.cmp(b))
+        });
+        paths
+    }
+
+    // Corporation of the current source, once its character lookup has returned it
+    fn source_corporation(&self) -> Option<i64> {
+        self.source_file()?.details.corporation_id
+    }
+
+    fn select_same_corporation(&mut self) {
+        let (Some(source_id), Some(corp_id)) = (self.source_character().map(str::to_string), self.source_corporation()) else {
+            return;
+        };
+        for file in &self.settings_files {
+            if file.character_id != source_id && file.details.corporation_id == Some(corp_id) {
+                self.copy_to.insert(file.path.clone());
+            }
+        }
+    }
+
+    // Replace the destinations with every other shown character whose file looks usable,
+    // for syncing all alts to one main
+    fn select_everyone_else(&mut self) {
+        let Some(source_id) = self.source_character().map(str::to_string) else {
+            return;
+        };
+        self.copy_to = self.settings_files.iter()
+            .filter(|f| f.character_id != source_id && !f.invalid && self.matches_filter(f))
+            .map(|f| f.path.clone())
+            .collect();
+    }
+
+    // Profile folders a new character file can be created in, with a label for each
+    fn profile_folders(&self) -> Vec<(PathBuf, String)> {
+        let show_install = !self.config.additional_paths.is_empty();
+        let mut folders: Vec<(PathBuf, String)> = Vec::new();
+        for file in &self.settings_files {
+            let Some(folder) = file.path.parent() else { continue };
+            if folders.iter().any(|(existing, _)| existing == folder) {
+                continue;
+            }
+            let label = if show_install {
+                format!("{} ({})", file.profile, install_label(&file.install))
+            } else {
+                file.profile.clone()
+            };
+            folders.push((folder.to_path_buf(), label));
+        }
+        folders.sort_by(|a, b| a.1.cmp(&b.1));
+        folders
+    }
+
+    // Folder the new file goes in: the one picked, else the source's
+    fn new_character_target_folder(&self) -> Option<PathBuf> {
+        self.new_character_folder.clone()
+            .or_else(|| self.copy_from.as_deref()?.parent().map(Path::to_path_buf))
+    }
+
+    fn create_new_character_file(&mut self) {
+        self.popup_title = "New Character".to_string();
+
+        let (Some(source_path), Some(folder)) = (self.copy_from.clone(), self.new_character_target_folder()) else {
+            self.popup_message = "Choose a source first".to_string();
+            self.popup_success = false;
+            self.show_popup = true;
+            return;
+        };
+        let new_id = self.new_character_id.trim().to_string();
+
+        let result = create_settings_file(&self.settings_files, &source_path, &new_id, &folder, self.copy_options);
+        let source_name = self.path_display_name(&source_path);
+        let header = format!("create {} from {} ({})", new_id, source_name, source_path.display());
+        let target = folder.join(format!("core_char_{}.dat", new_id)).display().to_string();
+        self.log_operation(log_file_results(header, &[(target, result.as_ref().map(|_| ()).map_err(|e| e.clone()))]));
+
+        match result {
+            Ok(path) => {
+                self.popup_message = format!("Created {} from {}'s settings", path.display(), source_name);
+                self.popup_success = true;
+                self.new_character_id.clear();
+                // Pick up the new file (and look its name up) without losing the selection
+                self.scan_files();
+            }
+            Err(e) => {
+                self.popup_message = e;
+                self.popup_success = false;
+            }
+        }
+        self.show_popup = true;
+    }
+
+    // Re-read expanded rows after their files may have changed on disk
+    fn refresh_file_details(&mut self) {
+        let paths: Vec<PathBuf> = self.file_details.keys().cloned().collect();
+        self.file_details.clear();
+        for path in paths {
+            if !self.settings_files.iter().any(|f| f.path == path) {
+                continue;
+            }
+            let summary = fs::read(&path)
+                .map(|contents| summarize_settings(&contents))
+                .map_err(|e| format!("Failed to read file: {}", e));
+            self.file_details.insert(path, summary);
+        }
+    }
+
+    // Remove a settings file from disk and from the list
+    fn delete_settings_file(&mut self, path: &Path) {
+        let character_id = match self.settings_files.iter().find(|f| f.path == path) {
+            Some(file) => file.character_id.clone(),
+            None => return,
+        };
+
+        self.popup_title = "Delete".to_string();
+        // The selection may have changed since the delete was requested
+        if self.copy_from.as_deref() == Some(path) {
+            self.popup_message = "The copy source can't be deleted".to_string();
+            self.popup_success = false;
+            self.show_popup = true;
+            return;
+        }
+
+        let removed = fs::remove_file(path);
+        let header = format!("delete {} ({})", self.path_display_name(path), character_id);
+        self.log_operation(log_file_results(header, &[
+            (path.display().to_string(), removed.as_ref().map(|_| ()).map_err(|e| e.to_string())),
+        ]));
+        if let Err(e) = removed {
+            self.popup_message = format!("Failed to delete {}: {}", path.display(), e);
+            self.popup_success = false;
+            self.show_popup = true;
+            return;
+        }
+
+        self.settings_files.retain(|f| f.path != path);
+        self.active_files = active_profile_files(&self.settings_files);
+        self.file_details.remove(path);
+        self.copy_to.remove(path);
+        // Another profile may still hold a file for the same character
+        if !self.settings_files.iter().any(|f| f.character_id == character_id) {
+            self.character_names.remove(&character_id);
+        }
+    }
+
+    // Save (or overwrite) a named profile from the current selection. Profiles
+    // remember characters, not files, so they survive profile folders changing.
+    fn save_copy_profile(&mut self, name: String) {
+        let mut destinations: Vec<String> = self.copy_to.iter()
+            .filter_map(|path| self.file_at(path))
+            .map(|f| f.character_id.clone())
+            .collect();
+        destinations.sort_by(|a, b| compare_character_ids(a, b));
+        destinations.dedup();
+        let source = self.source_character().map(str::to_string);
+        let profile = CopyProfile { name, source, destinations };
+
+        match self.config.copy_profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.config.copy_profiles.push(profile),
+        }
+        self.config.copy_profiles.sort_by_key(|p| p.name.to_lowercase());
+        self.save_config();
+    }
+
+    // Reselect a profile's characters, skipping any the current scan didn't find.
+    // A character with files in several profiles gets the first one in the list.
+    // A source in several profile folders is ambiguous, so the user picks the file first
+    fn load_copy_profile(&mut self, name: &str) {
+        let profile = match self.config.copy_profiles.iter().find(|p| p.name == name) {
+            Some(profile) => profile.clone(),
+            None => return,
+        };
+        if let Some(id) = &profile.source {
+            let choices = self.settings_files.iter()
+                .filter(|f| f.character_id == *id && !f.invalid)
+                .count();
+            if choices > 1 {
+                self.choose_source = Some(profile);
+                return;
+            }
+        }
+        self.apply_copy_profile(profile, None);
+    }
+
+    // Select a saved profile's characters. Each destination uses its file in the
+    // source's profile folder when it has one, the newest of its files otherwise.
+    fn apply_copy_profile(&mut self, profile: CopyProfile, source: Option<PathBuf>) {
+        let mut missing = Vec::new();
+        self.copy_from = match (source, profile.source.clone()) {
+            (Some(path), _) => Some(path),
+            (None, Some(id)) => {
+                let path = self.settings_files.iter()
+                    .find(|f| f.character_id == id && !f.invalid)
+                    .map(|f| f.path.clone());
+                if path.is_none() {
+                    missing.push(id);
+                }
+                path
+            }
+            (None, None) => None,
+        };
+        let source_folder = self.source_file().map(|f| (f.install.clone(), f.profile.clone()));
+
+        self.copy_to.clear();
+        for id in profile.destinations {
+            if profile.source.as_ref() == Some(&id) {
+                continue;
+            }
+            let candidates: Vec<&SettingsFile> = self.settings_files.iter()
+                .filter(|f| f.character_id == id)
+                .collect();
+            let matching = candidates.iter()
+                .find(|f| source_folder.as_ref() == Some(&(f.install.clone(), f.profile.clone())));
+            let newest = candidates.iter().max_by_key(|f| f.modified);
+            match matching.or(newest) {
+                Some(file) => {
+                    self.copy_to.insert(file.path.clone());
+                }
+                None => missing.push(id),
+            }
+        }
+        self.profile_name = profile.name.clone();
+
+        if !missing.is_empty() {
+            self.popup_title = "Copy Profile".to_string();
+            self.popup_message = format!("Loaded \"{}\", but these characters weren't found and were skipped: {}",
+                profile.name, missing.join(", "));
+            self.popup_success = false;
+            self.show_popup = true;
+        }
+    }
+
+    fn clear_all_destinations(&mut self) {
+        for path in self.displayed_paths() {
+            self.copy_to.remove(&path);
+        }
+    }
+
+    // Flip Copy To on every shown row, so "everyone but these" is two clicks
+    fn invert_destinations(&mut self) {
+        for path in self.displayed_paths() {
+            if self.copy_from.as_ref() == Some(&path) {
+                continue;
+            }
+            if !self.copy_to.remove(&path) {
+                self.copy_to.insert(path);
+            }
+        }
+    }
+
+    // (resolved, total) unique character IDs for the current scan
+    fn name_lookup_progress(&self) -> (usize, usize) {
+        let total = self.character_names.len();
+        let resolved = self.character_names
+            .values()
+            .filter(|v| !v.is_pending())
+            .count();
+        (resolved, total)
+    }
+
+    // Resolved name for a character, falling back to its ID
+    fn character_display_name(&self, character_id: &str) -> String {
+        self.settings_files.iter()
+            .find(|f| f.character_id == character_id)
+            .map(|f| match &f.character_name {
+                CharacterNameStatus::Found(n) => n.clone(),
+                // Don't pass an ID off as the name while the real one is on its way
+                CharacterNameStatus::Loading | CharacterNameStatus::Retrying => format!("{} (resolving…)", character_id),
+                _ => character_id.to_string(),
+            })
+            .unwrap_or_else(|| character_id.to_string())
+    }
+
+    // Name for one settings file: the character's name, plus the profile folder
+    // when that character has files in more than one
+    fn path_display_name(&self, path: &Path) -> String {
+        let Some(file) = self.file_at(path) else {
+            return path.display().to_string();
+        };
+        let name = self.character_display_name(&file.character_id);
+        let copies = self.settings_files.iter().filter(|f| f.character_id == file.character_id).count();
+        if copies > 1 {
+            format!("{} [{}]", name, file.profile)
+        } else {
+            name
+        }
+    }
+
+    // "Copying from Main to: AltA, AltB" for pasting into chat; IDs stand in for unresolved names
+    fn selection_summary(&self) -> Option<String> {
+        let name = |path: &Path| {
+            let file = self.file_at(path)?;
+            Some(match &file.character_name {
+                CharacterNameStatus::Found(name) => name.clone(),
+                _ => file.character_id.clone(),
+            })
+        };
+        let source = name(self.copy_from.as_deref()?)?;
+        let mut destinations: Vec<String> = self.copy_to.iter().filter_map(|path| name(path)).collect();
+        if destinations.is_empty() {
+            return None;
+        }
+        destinations.sort_by_key(|n| n.to_lowercase());
+        destinations.dedup();
+        Some(format!("Copying from {} to: {}", source, destinations.join(", ")))
+    }
+
+    // Gather what a copy would overwrite without writing anything
+    fn build_copy_preview(&self) -> Option<CopyPreview> {
+        let source = self.copy_from.clone()?;
+        let source_size = fs::metadata(&source).ok().map(|m| m.len());
+        let source_contents = fs::read(&source).ok();
+
+        let mut destinations: Vec<CopyPreviewEntry> = self.settings_files.iter()
+            .filter(|f| self.copy_to.contains(&f.path))
+            .map(|f| CopyPreviewEntry {
+                character_id: f.character_id.clone(),
+                path: f.path.clone(),
+                current_size: fs::metadata(&f.path).ok().map(|m| m.len()),
+                differing_bytes: source_contents.as_ref().and_then(|source| {
+                    fs::read(&f.path).ok().map(|dest| count_differing_bytes(source, &dest))
+                }),
+            })
+            .collect();
+        destinations.sort_by(|a, b| compare_character_ids(&a.character_id, &b.character_id)
+            .then_with(|| a.path.cmp(&b.path)));
+
+        Some(CopyPreview { source, source_size, destinations })
+    }
+
+    // Selected destinations whose file was modified after the source's, which
+    // a copy would roll back to older settings
+    fn find_downgrades(&self) -> Vec<PathBuf> {
+        let modified = |path: &Path| fs::metadata(path).ok().and_then(|m| m.modified().ok());
+        let source_modified = match self.copy_from.as_deref().and_then(modified) {
+            Some(time) => time,
+            None => return Vec::new(),
+        };
+
+        self.sorted_destinations().into_iter()
+            .filter(|path| modified(path).map(|time| time > source_modified).unwrap_or(false))
+            .collect()
+    }
+
+    // Advisory only: detection through Wine is unreliable, so copying stays allowed
+    fn show_client_running_warning(&self, ui: &mut egui::Ui) {
+        if !self.eve_running {
+            return;
+        }
+        ui.add_space(10.0);
+        ui.label(egui::RichText::new(
+            "⚠ EVE seems to be running. Log out of all characters first, or the client may overwrite the copied settings.")
+            .color(egui::Color32::from_rgb(230, 160, 0)));
+    }
+
+    // Shared by the confirm and preview dialogs. Returns whether the copy may go ahead.
+    fn show_downgrade_warning(&mut self, ui: &mut egui::Ui) -> bool {
+        if self.copy_downgrades.is_empty() {
+            return true;
+        }
+
+        let names: Vec<String> = self.copy_downgrades.iter()
+            .map(|path| self.path_display_name(path))
+            .collect();
+        ui.add_space(10.0);
+        let heading = if self.confirm_swap.is_some() {
+            "⚠ This side has newer settings, which the swap replaces with older ones:"
+        } else {
+            "⚠ These destinations have newer settings than the source:"
+        };
+        ui.label(egui::RichText::new(heading)
+            .color(egui::Color32::from_rgb(230, 160, 0)));
+        for name in &names {
+            ui.label(format!("  • {}", name));
+        }
+        ui.checkbox(&mut self.downgrade_acknowledged, "Overwrite them with the older settings anyway");
+        self.downgrade_acknowledged
+    }
+
+    // Shared by the confirm and preview dialogs; the copy may go ahead either way
+    fn show_read_only_warning(&mut self, ui: &mut egui::Ui) {
+        if self.copy_read_only.is_empty() {
+            return;
+        }
+
+        let names: Vec<String> = self.copy_read_only.iter()
+            .map(|path| match self.file_at(path) {
+                Some(_) => self.path_display_name(path),
+                None => path.display().to_string(),
+            })
+            .collect();
+        ui.add_space(10.0);
+        ui.label(egui::RichText::new("⚠ These files are read-only:")
+            .color(egui::Color32::from_rgb(230, 160, 0)));
+        for name in &names {
+            ui.label(format!("  • {}", name));
+        }
+        ui.checkbox(&mut self.copy_options.clear_read_only, "Clear the read-only flag and overwrite them");
+        if !self.copy_options.clear_read_only {
+            ui.label(egui::RichText::new("Left as they are, they may fail to copy.")
+                .color(egui::Color32::GRAY)
+                .italics());
+        }
+    }
+
+    // One row of the character list. Clicks are collected into `actions` and
+    // applied once the whole list has been drawn.
+    fn show_file_row(
+        &self,
+        ui: &mut egui::Ui,
+        file: &SettingsFile,
+        show_install: bool,
+        server_offline: bool,
+        actions: &mut RowActions,
+    ) {
+        let char_id = file.character_id.clone();
+        let is_copy_from = self.copy_from.as_ref() == Some(&file.path);
+        let is_copy_to = self.copy_to.contains(&file.path);
+
+        ui.horizontal(|ui| {
+            ui.add_space(10.0);
+
+            // Pin toggle; pinned characters are kept at the top of the list
+            let pinned = self.config.pinned.contains(&char_id);
+            let (star, hint) = if pinned {
+                (egui::RichText::new("★").color(egui::Color32::from_rgb(230, 160, 0)), "Unpin")
+            } else {
+                (egui::RichText::new("☆").color(egui::Color32::GRAY), "Pin to the top of the list")
+            };
+            if ui.add_sized([20.0, 20.0], egui::Button::new(star).frame(false))
+                .on_hover_text(hint)
+                .clicked()
+            {
+                actions.toggle_pin = Some(char_id.clone());
+            }
+
+            // Portrait, or a placeholder until (or if) it loads
+            let portrait_size = self.config.density.portrait_size();
+            match self.portraits.get(&char_id) {
+                Some(PortraitStatus::Loaded(texture)) => {
+                    ui.add(egui::Image::new(texture)
+                        .fit_to_exact_size(egui::vec2(portrait_size, portrait_size)));
+                }
+                _ => {
+                    ui.add_sized([portrait_size, portrait_size], egui::Label::new(
+                        egui::RichText::new("👤").color(egui::Color32::GRAY)
+                    ));
+                }
+            }
+            
+            // Filename, greyed out when the file is unusable as a source
+            let filename_text = if file.invalid {
+                egui::RichText::new(format!("⚠ {}", file.filename))
+                    .color(egui::Color32::GRAY)
+            } else {
+                egui::RichText::new(&file.filename)
+            };
+            // The full path tells apart files for the same character in different profiles
+            let filename_label = ui.add_sized([200.0, 20.0], egui::Label::new(filename_text));
+            if file.invalid {
+                filename_label.on_hover_text(format!(
+                    "{}\nFile is empty or truncated ({}) and can't be used as a source",
+                    file.path.display(), format_size(file.size)));
+            } else {
+                filename_label.on_hover_text(file.path.display().to_string());
+            }
+            
+            // Character ID
+            let id_text = if file.invalid {
+                egui::RichText::new(&file.character_id).color(egui::Color32::GRAY)
+            } else {
+                egui::RichText::new(&file.character_id)
+            };
+            ui.add_sized([120.0, 20.0], egui::Label::new(id_text));
+            
+            // Character name with status
+            let user_named = self.name_cache.get(&char_id).is_some_and(|entry| entry.user_provided);
+            let from_roster = self.roster_names.contains_key(&char_id);
+            let name_text = match &file.character_name {
+                CharacterNameStatus::Loading if server_offline => {
+                    egui::RichText::new("Server offline")
+                        .color(egui::Color32::GRAY)
+                        .italics()
+                }
+                CharacterNameStatus::Loading => {
+                    egui::RichText::new("Loading...")
+                        .color(egui::Color32::GRAY)
+                        .italics()
+                }
+                CharacterNameStatus::Retrying => {
+                    egui::RichText::new("Rate limited, retrying...")
+                        .color(egui::Color32::GRAY)
+                        .italics()
+                }
+                CharacterNameStatus::Found(name) if from_roster => {
+                    egui::RichText::new(format!("📋 {}", name))
+                        .color(success_color(ui))
+                }
+                CharacterNameStatus::Found(name) if user_named => {
+                    egui::RichText::new(format!("✎ {}", name))
+                        .italics()
+                }
+                CharacterNameStatus::Found(name) => {
+                    egui::RichText::new(name)
+                        .color(success_color(ui))
+                }
+                CharacterNameStatus::Error(err) => {
+                    egui::RichText::new(format!("✗ {}", err))
+                        .color(egui::Color32::RED)
+                }
+                CharacterNameStatus::Cancelled => {
+                    egui::RichText::new("Cancelled")
+                        .color(egui::Color32::GRAY)
+                }
+                CharacterNameStatus::Offline => {
+                    egui::RichText::new("(offline)")
+                        .color(egui::Color32::GRAY)
+                }
+                CharacterNameStatus::Deleted => {
+                    egui::RichText::new("deleted?")
+                        .color(egui::Color32::GRAY)
+                        .italics()
+                }
+            };
+            let is_error = matches!(file.character_name,
+                CharacterNameStatus::Error(_) | CharacterNameStatus::Cancelled | CharacterNameStatus::Deleted);
+            let editing_text = self.editing_name.as_ref()
+                .filter(|(path, _)| *path == file.path)
+                .map(|(_, text)| text.clone());
+            ui.add_sized([150.0, 20.0], |ui: &mut egui::Ui| {
+                ui.horizontal(|ui| {
+                    // Typing a name in place of one ESI couldn't resolve
+                    if let Some(mut text) = editing_text {
+                        let response = ui.add(egui::TextEdit::singleline(&mut text)
+                            .hint_text("Name; empty to clear")
+                            .desired_width(140.0));
+                        if response.changed() {
+                            actions.name_text = Some(text);
+                        }
+                        if response.lost_focus() {
+                            if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                                actions.name_commit = true;
+                            } else {
+                                actions.name_cancel = true;
+                            }
+                        } else if !response.has_focus() {
+                            response.request_focus();
+                        }
+                        return;
+                    }
+
+                    // Failed lookups can be retried individually
+                    if is_error && ui.small_button("↻").on_hover_text("Retry lookup").clicked() {
+                        actions.retry_id = Some(char_id.clone());
+                    }
+                    if user_named || matches!(file.character_name,
+                        CharacterNameStatus::Error(_) | CharacterNameStatus::Deleted)
+                    {
+                        let hint = if user_named {
+                            "Name entered by hand; double-click to change it"
+                        } else if matches!(file.character_name, CharacterNameStatus::Deleted) {
+                            "ESI doesn't know this character (404), so it was probably biomassed. \
+                             It isn't looked up again for 30 days unless you retry.\n\
+                             Double-click to type a name for it"
+                        } else {
+                            "Double-click to type a name for this character"
+                        };
+                        let label = ui.add(egui::Label::new(name_text).sense(egui::Sense::click()))
+                            .on_hover_text(hint);
+                        if label.double_clicked() {
+                            actions.edit_name = Some(file.path.clone());
+                        }
+                    } else if matches!(file.character_name, CharacterNameStatus::Found(_)) {
+                        let expiry = match file.details.expires_at {
+                            Some(expires_at) if expires_at > unix_now() => format!("Cached by ESI until {} (EVE time)",
+                                format_timestamp(Some(UNIX_EPOCH + Duration::from_secs(expires_at)))),
+                            Some(expires_at) => format!("ESI's copy expired {} (EVE time); the next scan or Refresh expired looks it up again",
+                                format_timestamp(Some(UNIX_EPOCH + Duration::from_secs(expires_at)))),
+                            None => "No expiry known for this name; it is looked up again a day after it was cached".to_string(),
+                        };
+                        ui.label(name_text).on_hover_text(expiry);
+                    } else {
+                        ui.label(name_text);
+                    }
+                }).response
+            });
+
+            // Installation the file came from
+            if show_install {
+                ui.add_sized([110.0, 20.0], egui::Label::new(install_label(&file.install)))
+                    .on_hover_text(file.install.display().to_string());
+            }
+
+            // Corporation name, once the character lookup has told us the corp ID
+            let corp_status = match file.details.corporation_id {
+                Some(corp_id) => self.corporation_names.get(&corp_id),
+                None => None,
+            };
+            let corp_text = match (corp_status, &file.character_name) {
+                (Some(CorporationStatus::Found(name)), _) => {
+                    egui::RichText::new(name)
+                        .color(success_color(ui))
+                }
+                (Some(CorporationStatus::Error(err)), _) => {
+                    egui::RichText::new(format!("✗ {}", err))
+                        .color(egui::Color32::RED)
+                }
+                (Some(CorporationStatus::Cancelled), _)
+                | (None, CharacterNameStatus::Cancelled) => {
+                    egui::RichText::new("Cancelled")
+                        .color(egui::Color32::GRAY)
+                }
+                (None, CharacterNameStatus::Error(_) | CharacterNameStatus::Offline | CharacterNameStatus::Deleted) => {
+                    egui::RichText::new("—")
+                        .color(egui::Color32::GRAY)
+                }
+                _ => {
+                    egui::RichText::new("Loading...")
+                        .color(egui::Color32::GRAY)
+                        .italics()
+                }
+            };
+            ui.add_sized([150.0, 20.0], egui::Label::new(corp_text));
+
+            // Alliance, once the corporation lookup has told us the alliance ID
+            let alliance_status = match (corp_status, file.details.corporation_id) {
+                (Some(CorporationStatus::Found(_)), Some(corp_id)) => match self.corporation_alliances.get(&corp_id) {
+                    Some(Some(alliance_id)) => Some(self.alliance_names.get(alliance_id)),
+                    // NPC corporations and independent player corporations
+                    Some(None) => None,
+                    None => Some(None),
+                },
+                (Some(CorporationStatus::Loading), _) => Some(None),
+                (None, _) if file.character_name.is_pending() => Some(None),
+                (Some(CorporationStatus::Cancelled), _) => Some(Some(&CorporationStatus::Cancelled)),
+                _ => None,
+            };
+            let alliance_text = match alliance_status {
+                Some(Some(AllianceStatus::Found(name))) => {
+                    egui::RichText::new(name)
+                        .color(success_color(ui))
+                }
+                Some(Some(AllianceStatus::Error(err))) => {
+                    egui::RichText::new(format!("✗ {}", err))
+                        .color(egui::Color32::RED)
+                }
+                Some(Some(AllianceStatus::Cancelled)) => {
+                    egui::RichText::new("Cancelled")
+                        .color(egui::Color32::GRAY)
+                }
+                Some(Some(AllianceStatus::Loading)) | Some(None) => {
+                    egui::RichText::new("Loading...")
+                        .color(egui::Color32::GRAY)
+                        .italics()
+                }
+                None => {
+                    egui::RichText::new("—")
+                        .color(egui::Color32::GRAY)
+                }
+            };
+            ui.add_sized([150.0, 20.0], egui::Label::new(alliance_text));
+
+            // Character age
+            let age_text = match &file.character_name {
+                CharacterNameStatus::Loading | CharacterNameStatus::Retrying => egui::RichText::new("Loading...")
+                    .color(egui::Color32::GRAY)
+                    .italics(),
+                _ => egui::RichText::new(format_character_age(file.details.birthday.as_deref())),
+            };
+            // Bulk-resolved names have no birthday or security status until the row is expanded
+            let details_hint = (matches!(file.character_name, CharacterNameStatus::Found(_))
+                && file.details.birthday.is_none())
+                .then_some("Expand the row (▶) to look this up");
+            let age_label = ui.add_sized([90.0, 20.0], egui::Label::new(age_text));
+            if let Some(hint) = details_hint {
+                age_label.on_hover_text(hint);
+            }
+
+            // Security status; negative means suspect or criminal trouble in high-sec
+            let security_text = match (&file.character_name, file.details.security_status) {
+                (CharacterNameStatus::Loading | CharacterNameStatus::Retrying, _) => egui::RichText::new("Loading...")
+                    .color(egui::Color32::GRAY)
+                    .italics(),
+                (_, Some(security)) if security < 0.0 => egui::RichText::new(format!("{:.1}", security))
+                    .color(egui::Color32::RED),
+                (_, Some(security)) => egui::RichText::new(format!("{:.1}", security))
+                    .color(success_color(ui)),
+                (_, None) => egui::RichText::new("—")
+                    .color(egui::Color32::GRAY),
+            };
+            let security_label = ui.add_sized([60.0, 20.0], egui::Label::new(security_text));
+            if let Some(hint) = details_hint.filter(|_| file.details.security_status.is_none()) {
+                security_label.on_hover_text(hint);
+            }
+
+            // Last modified, marked on the profile EVE last wrote for this character
+            let time_display = self.config.time_display;
+            let other_time = time_display.other().format(file.modified);
+            if self.active_files.contains(&file.path) {
+                ui.add_sized([130.0, 20.0], egui::Label::new(
+                    egui::RichText::new(format!("● {}", time_display.format(file.modified)))
+                        .color(success_color(ui))))
+                    .on_hover_text(format!("{}\nActive profile: the newest of this character's files, so EVE is most likely using {}",
+                        other_time, file.profile));
+            } else {
+                ui.add_sized([130.0, 20.0], egui::Label::new(time_display.format(file.modified)))
+                    .on_hover_text(other_time);
+            }
+            
+            // Copy To checkbox (disabled if this is the copy_from source)
+            let mut to_checked = is_copy_to;
+            ui.add_sized([60.0, 20.0], |ui: &mut egui::Ui| {
+                ui.add_enabled_ui(!is_copy_from, |ui| {
+                    let checkbox = ui.checkbox(&mut to_checked, "");
+                    if checkbox.changed() {
+                        if ui.input(|i| i.modifiers.shift) {
+                            actions.copy_to_range = Some((file.path.clone(), to_checked));
+                        } else if to_checked {
+                            actions.copy_to_add = Some(file.path.clone());
+                        } else {
+                            actions.copy_to_remove = Some(file.path.clone());
+                        }
+                    }
+                });
+                ui.response()
+            });
+
+            let make_source = ui.add_enabled(!is_copy_from && !file.invalid, egui::Button::new("⬆").small())
+                .on_hover_text("Set as source: copy from this character instead")
+                .on_disabled_hover_text(if file.invalid {
+                    "Empty or truncated files can't be copied from"
+                } else {
+                    "This is already the source"
+                });
+            if make_source.clicked() {
+                actions.set_source = Some(file.path.clone());
+            }
+
+            if ui.small_button("📂").on_hover_text("Show in file manager").clicked() {
+                actions.reveal_path = Some(file.path.clone());
+            }
+
+            // Notes show on hover; the icon is only bright when there is one
+            let note = self.notes.get(&char_id);
+            let note_icon = match note {
+                Some(_) => egui::RichText::new("📝"),
+                None => egui::RichText::new("📝").color(egui::Color32::GRAY),
+            };
+            let hover = note.cloned().unwrap_or_else(|| "Add a note".to_string());
+            if ui.small_button(note_icon).on_hover_text(hover).clicked() {
+                actions.edit_note = Some(char_id.clone());
+            }
+
+            let delete = ui.add_enabled(!is_copy_from, egui::Button::new("🗑").small())
+                .on_hover_text("Delete this settings file")
+                .on_disabled_hover_text("The copy source can't be deleted");
+            if delete.clicked() {
+                actions.delete_path = Some(file.path.clone());
+            }
+
+            let details = self.file_details.get(&file.path);
+            let toggle = if details.is_some() { "▼" } else { "▶" };
+            if ui.small_button(toggle).on_hover_text("Show what's in this file").clicked() {
+                actions.toggle_details = Some(file.path.clone());
+            }
+        });
+
+        if let Some(details) = self.file_details.get(&file.path) {
+            show_settings_summary(ui, &file.path, details);
+        }
+        
+        ui.add_space(self.config.density.row_gap());
+    }
+
+    // `display_order` is the rows as drawn, which shift-click ranges follow
+    fn apply_row_actions(&mut self, actions: RowActions, display_order: &[usize]) {
+        if let Some(path) = actions.set_source {
+            // The source can't also be a destination
+            self.copy_to.remove(&path);
+            self.copy_from = Some(path);
+        }
+        if let Some(path) = actions.copy_to_add {
+            self.last_toggled = Some(path.clone());
+            self.copy_to.insert(path);
+        }
+        if let Some(path) = actions.copy_to_remove {
+            self.copy_to.remove(&path);
+            self.last_toggled = Some(path);
+        }
+        if let Some((path, checked)) = actions.copy_to_range {
+            self.apply_copy_to_range(&path, checked, display_order);
+        }
+        if let Some(id) = actions.retry_id {
+            self.retry_lookup(&id);
+        }
+        if let Some(id) = actions.toggle_pin {
+            if !self.config.pinned.remove(&id) {
+                self.config.pinned.insert(id);
+            }
+            self.save_config();
+            self.sort_files();
+        }
+        if let Some(path) = actions.delete_path {
+            self.pending_delete = Some(path);
+        }
+        if let Some(path) = actions.toggle_details {
+            if self.file_details.remove(&path).is_none() {
+                let summary = fs::read(&path)
+                    .map(|contents| summarize_settings(&contents))
+                    .map_err(|e| format!("Failed to read file: {}", e));
+                self.lookup_missing_details(&path);
+                self.file_details.insert(path, summary);
+            }
+        }
+        if let Some(path) = actions.edit_name {
+            let text = match self.file_at(&path).map(|f| &f.character_name) {
+                Some(CharacterNameStatus::Found(name)) => name.clone(),
+                _ => String::new(),
+            };
+            self.editing_name = Some((path, text));
+        }
+        if let Some(text) = actions.name_text {
+            if let Some((_, editing)) = &mut self.editing_name {
+                *editing = text;
+            }
+        }
+        if actions.name_commit {
+            if let Some((path, text)) = self.editing_name.take() {
+                self.set_manual_name(&path, &text);
+            }
+        } else if actions.name_cancel {
+            self.editing_name = None;
+        }
+        if let Some(id) = actions.edit_note {
+            let text = self.notes.get(&id).cloned().unwrap_or_default();
+            self.editing_note = Some((id, text));
+        }
+        if let Some(path) = actions.reveal_path {
+            if let Err(e) = reveal_in_file_manager(&path) {
+                self.popup_title = "Open Folder".to_string();
+                self.popup_message = e;
+                self.popup_success = false;
+                self.show_popup = true;
+            }
+        }
+    }
+
+    // Visible rows grouped by resolved corporation name, with unresolved ones last
+    // Each group's corporation ID (None for characters without one), header label and rows
+    fn corporation_groups(&self, visible_files: &[usize]) -> Vec<(Option<i64>, String, Vec<usize>)> {
+        // By ID, so two corporations that share a name stay apart
+        let mut groups: Vec<(Option<i64>, Vec<usize>)> = Vec::new();
+        for &index in visible_files {
+            let corp_id = self.settings_files[index].details.corporation_id;
+            match groups.iter_mut().find(|(id, _)| *id == corp_id) {
+                Some((_, members)) => members.push(index),
+                None => groups.push((corp_id, vec![index])),
+            }
+        }
+
+        let label = |corp_id: Option<i64>| match corp_id {
+            Some(corp_id) => match self.corporation_names.get(&corp_id) {
+                Some(CorporationStatus::Found(name)) => name.clone(),
+                _ => format!("Corporation {}", corp_id),
+            },
+            None => "Unknown".to_string(),
+        };
+        let mut labelled: Vec<(Option<i64>, String, Vec<usize>)> = groups.into_iter()
+            .map(|(corp_id, members)| (corp_id, label(corp_id), members))
+            .collect();
+        // Characters without a corporation go last
+        labelled.sort_by(|a, b| a.0.is_none().cmp(&b.0.is_none())
+            .then_with(|| a.1.to_lowercase().cmp(&b.1.to_lowercase()))
+            .then_with(|| a.0.cmp(&b.0)));
+        labelled
+    }
+
+    // One line like "3 corps: Pandemic Horde (12), ..." from the corporations resolved so far
+    fn corporation_summary(&self) -> Option<String> {
+        let mut members: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for file in &self.settings_files {
+            let Some(corp_id) = file.details.corporation_id else { continue };
+            if let Some(CorporationStatus::Found(name)) = self.corporation_names.get(&corp_id) {
+                members.entry(name.as_str()).or_default().insert(file.character_id.as_str());
+            }
+        }
+        if members.is_empty() {
+            return None;
+        }
+
+        let mut counts: Vec<(&str, usize)> = members.into_iter()
+            .map(|(name, characters)| (name, characters.len()))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.to_lowercase().cmp(&b.0.to_lowercase())));
+
+        let mut parts: Vec<String> = counts.iter()
+            .take(CORP_SUMMARY_LIMIT)
+            .map(|(name, count)| format!("{} ({})", name, count))
+            .collect();
+        if counts.len() > CORP_SUMMARY_LIMIT {
+            parts.push(format!("+{} more", counts.len() - CORP_SUMMARY_LIMIT));
+        }
+        let noun = if counts.len() == 1 { "corp" } else { "corps" };
+        Some(format!("{} {}: {}", counts.len(), noun, parts.join(", ")))
+    }
+
+    fn can_copy(&self) -> bool {
+        self.copy_from.is_some()
+            && !self.copy_to.is_empty()
+            && !(self.config.wait_for_names && self.selection_names_pending())
+    }
+
+    // Whether the source or any destination is still waiting for its name
+    fn selection_names_pending(&self) -> bool {
+        self.copy_from.iter()
+            .chain(self.copy_to.iter())
+            .filter_map(|path| self.file_at(path))
+            .any(|f| f.character_name.is_pending())
+    }
+
+    // Fresh scan, dropping the current list and selection
+    fn rescan(&mut self) {
+        self.scan_complete = false;
+        self.settings_files.clear();
+        self.character_names.clear();
+        self.copy_from = None;
+        self.copy_to.clear();
+        self.scan_files();
+    }
+
+    // Keep the watcher on the paths of the current scan, and rescan when it reports
+    // a change. The selection survives, less any files that have gone.
+    fn update_watcher(&mut self, ctx: &egui::Context) {
+        let mut roots = vec![self.eve_path.clone()];
+        roots.extend(self.config.additional_paths.iter().cloned());
+        if self.scan_job.is_some() {
+            return;
+        }
+        if !self.config.auto_rescan || !self.scan_complete || self.settings_files.is_empty() {
+            self.watcher = None;
+            return;
+        }
+        if self.watcher.as_ref().is_none_or(|watcher| watcher.roots != roots) {
+            self.watcher = Some(SettingsWatcher::start(roots, self.config.scan_depth(), ctx.clone()));
+        }
+
+        // Changes made while a dialog is up wait until it closes
+        if self.dialog_open() {
+            return;
+        }
+        let Some(watcher) = &self.watcher else {
+            return;
+        };
+        if watcher.receiver.try_iter().count() == 0 {
+            return;
+        }
+        self.scan_files();
+    }
+
+    // Start a copy the way the Copy Settings button does: via the preview or the confirmation
+    fn request_copy(&mut self) {
+        // Only checked here, since walking the process list isn't free
+        self.eve_running = eve_client_running();
+        self.copy_downgrades = self.find_downgrades();
+        self.downgrade_acknowledged = false;
+        self.copy_read_only = match &self.copy_from {
+            Some(source) => read_only_destinations(&self.settings_files, source, &self.sorted_destinations(), self.copy_options),
+            None => Vec::new(),
+        };
+        // Opt-in for each copy
+        self.copy_options.clear_read_only = false;
+        if self.preview_mode {
+            self.copy_preview = self.build_copy_preview();
+        } else {
+            self.confirm_copy = true;
+        }
+    }
+
+    // Any window that expects an answer before the main view is used again
+    fn dialog_open(&self) -> bool {
+        self.show_popup
+            || self.confirm_copy
+            || self.confirm_swap.is_some()
+            || self.copy_preview.is_some()
+            || self.pending_delete.is_some()
+            || self.confirm_create
+            || self.editing_note.is_some()
+            || self.restore_plan.is_some()
+            || self.show_about
+            || self.show_history
+            || self.choose_source.is_some()
+            || self.settings_diff.is_some()
+            || self.file_dialog.is_some()
+    }
+
+    fn browse_for_path(&mut self) {
+        let start_dir = self.eve_path.clone();
+        self.open_file_dialog(|| pick_folder(FileDialogPurpose::EvePath, &start_dir));
+    }
+
+    // Only one file dialog at a time; a second click while one is up does nothing
+    fn open_file_dialog(&mut self, open: impl FnOnce() -> FileDialog) {
+        if self.file_dialog.is_none() {
+            self.file_dialog = Some(open());
+        }
+    }
+
+    // Hand the chosen path to whatever opened the file dialog, once it closes
+    fn poll_file_dialog(&mut self) {
+        let Some(dialog) = &self.file_dialog else {
+            return;
+        };
+        let purpose = dialog.purpose;
+        let result = match dialog.receiver.try_recv() {
+            Ok(result) => result,
+            Err(std::sync::mpsc::TryRecvError::Empty) => return,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => Err("The file dialog stopped unexpectedly".to_string()),
+        };
+        self.file_dialog = None;
+
+        let path = match result {
+            Ok(Some(path)) => path,
+            // Dialog was cancelled
+            Ok(None) => return,
+            Err(e) => {
+                self.popup_title = purpose.title().to_string();
+                self.popup_message = e;
+                self.popup_success = false;
+                self.show_popup = true;
+                return;
+            }
+        };
+        match purpose {
+            FileDialogPurpose::EvePath => {
+                self.eve_path = path.to_string_lossy().to_string();
+                self.path_notice = None;
+                self.scan_complete = false;
+            }
+            FileDialogPurpose::AddInstall => {
+                self.new_install_path = path.to_string_lossy().to_string();
+            }
+            FileDialogPurpose::NamesFile => {
+                self.config.names_file = path.to_string_lossy().to_string();
+                self.save_config();
+                self.scan_complete = false;
+            }
+            FileDialogPurpose::ExportCsv => self.export_csv_to(path),
+            FileDialogPurpose::BackupAll => self.backup_all_to(path),
+            FileDialogPurpose::Restore => self.restore_from(path),
+        }
+    }
+
+    // File and Help menus; the file actions only make sense once something was found
+    fn show_menu_bar(&mut self, ctx: &egui::Context) {
+        let has_files = !self.settings_files.is_empty();
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button("File", |ui| {
+                    if ui.add(egui::Button::new("🔍 Scan").shortcut_text("Ctrl+R")).clicked() {
+                        ui.close_menu();
+                        self.rescan();
+                    }
+                    if ui.button("📁 Browse…").clicked() {
+                        ui.close_menu();
+                        self.browse_for_path();
+                    }
+                    ui.separator();
+                    if ui.add_enabled(has_files, egui::Button::new("📄 Export CSV")).clicked() {
+                        ui.close_menu();
+                        self.export_csv();
+                    }
+                    if ui.add_enabled(has_files, egui::Button::new("💾 Backup All"))
+                        .on_hover_text("Save every settings file into a single zip")
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.backup_all();
+                    }
+                    if ui.add_enabled(has_files, egui::Button::new("📦 Restore from Zip…")).clicked() {
+                        ui.close_menu();
+                        self.open_restore();
+                    }
+                    ui.separator();
+                    if ui.button("📜 Operation Log")
+                        .on_hover_text("Show the log of copies, swaps and deletes")
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.open_operation_log();
+                    }
+                    if ui.button("🕘 Copy History")
+                        .on_hover_text("Recent copies, to run one again")
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.show_history = true;
+                    }
+                    ui.separator();
+                    if ui.button("Quit").clicked() {
+                        ui.close_menu();
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                });
+                ui.menu_button("View", |ui| {
+                    for (density, label) in [(Density::Comfortable, "Comfortable rows"), (Density::Compact, "Compact rows")] {
+                        if ui.radio(self.config.density == density, label).clicked() {
+                            ui.close_menu();
+                            self.config.density = density;
+                            self.save_config();
+                        }
+                    }
+                    ui.separator();
+                    for (time_display, label) in [(TimeDisplay::Absolute, "Dates and times"), (TimeDisplay::Relative, "Relative times")] {
+                        if ui.radio(self.config.time_display == time_display, label).clicked() {
+                            ui.close_menu();
+                            self.config.time_display = time_display;
+                            self.save_config();
+                        }
+                    }
+                });
+                ui.menu_button("Help", |ui| {
+                    if ui.button("About ToonSettings").clicked() {
+                        ui.close_menu();
+                        self.show_about = true;
+                    }
+                });
+            });
+        });
+    }
+
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        // Keys pressed while a dialog is up are left for the dialog
+        if self.dialog_open() {
+            return;
+        }
+
+        let (scan, copy) = ctx.input_mut(|i| {
+            let scan = i.consume_key(egui::Modifiers::COMMAND, egui::Key::R)
+                | i.consume_key(egui::Modifiers::NONE, egui::Key::F5);
+            let copy = i.consume_key(egui::Modifiers::COMMAND, egui::Key::Enter);
+            (scan, copy)
+        });
+        if scan {
+            self.rescan();
+        } else if copy && self.can_copy() {
+            self.request_copy();
+        }
+    }
+}
+
+// The expanded part of a row: format, readable text and how much is left as binary
+fn show_settings_summary(ui: &mut egui::Ui, path: &Path, details: &Result<SettingsSummary, String>) {
+    ui.indent(("settings_summary", path), |ui| {
+        let summary = match details {
+            Ok(summary) => summary,
+            Err(e) => {
+                ui.colored_label(egui::Color32::RED, e);
+                return;
+            }
+        };
+
+        let format = if summary.marshal { "EVE marshal data" } else { "Unrecognised format" };
+        ui.label(egui::RichText::new(format!("{} - {} bytes", format, summary.size)).strong());
+        if summary.strings.is_empty() {
+            ui.label(egui::RichText::new("No readable text").color(egui::Color32::GRAY));
+        } else {
+            ui.label(format!("Readable text ({} entries):", summary.strings.len()));
+            egui::ScrollArea::vertical()
+                .id_salt(("settings_summary_text", path))
+                .max_height(120.0)
+                .show(ui, |ui| {
+                    ui.label(egui::RichText::new(summary.strings.join(", ")).monospace());
+                });
+        }
+        ui.label(egui::RichText::new(format!("binary: {} bytes", summary.binary_bytes))
+            .color(egui::Color32::GRAY));
+    });
+}
+
+impl eframe::App for EveSettingsApp {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // The writer thread wouldn't outlive the process, so wait for it
+        self.save_config();
+        self.writer.flush();
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Process any pending API messages
+        self.process_api_messages(ctx);
+        self.poll_scan();
+        self.poll_file_dialog();
+
+        // Before the panels are drawn, so text boxes don't see the shortcut keys
+        self.handle_shortcuts(ctx);
+        self.update_watcher(ctx);
+
+        // Track the window geometry so it can be restored next launch
+        let (inner_rect, outer_rect) = ctx.input(|i| (i.viewport().inner_rect, i.viewport().outer_rect));
+        if let Some(rect) = inner_rect {
+            self.config.window_size = Some([rect.width(), rect.height()]);
+        }
+        if let Some(rect) = outer_rect {
+            self.config.window_position = Some([rect.min.x, rect.min.y]);
+        }
+
+        // Request repaint while loading
+        let has_loading = self.character_names.values().any(CharacterNameStatus::is_pending)
+            || self.corporation_names.values().any(|v| matches!(v, CorporationStatus::Loading))
+            || self.alliance_names.values().any(|v| matches!(v, AllianceStatus::Loading))
+            || (!self.config.offline && self.settings_files.iter().any(|f| !self.portraits.contains_key(&f.character_id)))
+            || (!self.config.offline && !self.settings_files.is_empty() && self.server_status.is_none())
+            || self.scan_job.is_some()
+            || self.file_dialog.is_some();
+        if has_loading {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        } else if self.config.time_display == TimeDisplay::Relative {
+            // Keeps "just now" and "5 minutes ago" moving while the window is idle
+            ctx.request_repaint_after(Duration::from_secs(30));
+        }
+
+        // Configure custom styling - matching ToonTab colour scheme
+        // ToonTab uses the default egui dark theme; light mode is opt-in
+        let mut style = (*ctx.style()).clone();
+        style.visuals = match self.config.theme {
+            Theme::Dark => egui::Visuals::dark(),
+            Theme::Light => egui::Visuals::light(),
+        };
+        ctx.set_style(style);
+
+        // A folder dropped onto the window becomes the new settings path
+        let dropped_folder = ctx.input(|i| {
+            i.raw.dropped_files.iter()
+                .filter_map(|f| f.path.clone())
+                .find(|path| path.is_dir())
+        });
+        if let Some(folder) = dropped_folder {
+            self.eve_path = folder.to_string_lossy().to_string();
+            self.path_notice = None;
+            self.scan_complete = false;
+        }
+
+        // Hint while something is being dragged over the window
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                egui::Id::new("drop_hint"),
+            ));
+            let screen_rect = ctx.screen_rect();
+            painter.rect_filled(screen_rect, 0.0, egui::Color32::from_black_alpha(192));
+            painter.text(
+                screen_rect.center(),
+                egui::Align2::CENTER_CENTER,
+                "Drop an EVE settings folder here",
+                egui::TextStyle::Heading.resolve(&ctx.style()),
+                egui::Color32::WHITE,
+            );
+        }
+
+        // Confirmation before overwriting anything, for a copy or a swap
+        if self.confirm_copy || self.confirm_swap.is_some() {
+            let mut confirmed = false;
+            let mut cancelled = false;
+
+            let swap = self.confirm_swap.clone();
+            let (title, heading, source_path, note) = match &swap {
+                Some(_) => (
+                    "Confirm Swap",
+                    "Exchange the settings of:".to_string(),
+                    None,
+                    "Each file will be overwritten with the other's settings.",
+                ),
+                None => (
+                    "Confirm Copy",
+                    format!("Copy settings from {} to:", self.copy_from.as_deref()
+                        .map(|path| self.path_display_name(path))
+                        .unwrap_or_default()),
+                    self.copy_from.as_deref().map(|path| path.display().to_string()),
+                    "The destination settings will be overwritten.",
+                ),
+            };
+            let mut destinations: Vec<(String, String)> = match &swap {
+                Some((first, second)) => vec![first.clone(), second.clone()],
+                None => self.copy_to.iter().cloned().collect(),
+            }
+                .iter()
+                .map(|path| (self.path_display_name(path), path.display().to_string()))
+                .collect();
+            destinations.sort();
+
+            egui::Window::new(title)
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.add_space(10.0);
+
+                    ui.label(heading);
+                    if let Some(source_path) = &source_path {
+                        ui.label(egui::RichText::new(source_path)
+                            .small()
+                            .color(egui::Color32::GRAY));
+                    }
+                    ui.add_space(5.0);
+                    for (name, path) in &destinations {
+                        ui.label(format!("  • {}", name));
+                        ui.label(egui::RichText::new(format!("     {}", path))
+                            .small()
+                            .color(egui::Color32::GRAY));
+                    }
+
+                    ui.add_space(10.0);
+                    ui.label(egui::RichText::new(note)
+                        .color(egui::Color32::GRAY)
+                        .italics());
+                    self.show_client_running_warning(ui);
+                    self.show_read_only_warning(ui);
+                    let can_confirm = self.show_downgrade_warning(ui);
+                    ui.add_space(15.0);
+
+                    ui.vertical_centered(|ui| {
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(can_confirm, egui::Button::new("  Confirm  ")).clicked() {
+                                confirmed = true;
+                            }
+                            if ui.button("  Cancel  ").clicked() {
+                                cancelled = true;
+                            }
+                        });
+                    });
+
+                    ui.add_space(5.0);
+                });
+
+            if confirmed {
+                self.confirm_copy = false;
+                match self.confirm_swap.take() {
+                    Some((first, second)) => self.swap_settings(first, second),
+                    None => self.copy_settings(),
+                }
+            } else if cancelled {
+                self.confirm_copy = false;
+                self.confirm_swap = None;
+            }
+        }
+
+        // Dry-run preview of a pending copy
+        if let Some(preview) = self.copy_preview.clone() {
+            let mut confirmed = false;
+            let mut cancelled = false;
+
+            egui::Window::new("Copy Preview")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.add_space(10.0);
+
+                    ui.label(format!("Source: {} ({})",
+                        self.path_display_name(&preview.source),
+                        format_size(preview.source_size)));
+                    ui.label(egui::RichText::new(preview.source.display().to_string())
+                        .small()
+                        .color(egui::Color32::GRAY));
+                    ui.add_space(5.0);
+                    ui.label(format!("{} file(s) would be overwritten:", preview.destinations.len()));
+                    ui.add_space(5.0);
+
+                    egui::Grid::new("copy_preview_grid")
+                        .striped(true)
+                        .spacing([15.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label(egui::RichText::new("Character").strong());
+                            ui.label(egui::RichText::new("Destination").strong());
+                            ui.label(egui::RichText::new("Current Size").strong());
+                            ui.label(egui::RichText::new("Difference").strong());
+                            ui.end_row();
+
+                            for dest in &preview.destinations {
+                                ui.label(self.path_display_name(&dest.path));
+                                ui.label(dest.path.display().to_string());
+                                let size_text = format_size(dest.current_size);
+                                if dest.current_size.is_some() && dest.current_size == preview.source_size {
+                                    ui.label(format!("{} (same)", size_text));
+                                } else {
+                                    ui.label(size_text);
+                                }
+                                match dest.differing_bytes {
+                                    Some(0) if self.copy_options.skip_identical => {
+                                        ui.label(egui::RichText::new("✓ Identical, skipped")
+                                            .color(success_color(ui)));
+                                    }
+                                    Some(0) => {
+                                        ui.label(egui::RichText::new("✓ Identical")
+                                            .color(success_color(ui)));
+                                    }
+                                    Some(count) => {
+                                        ui.label(format!("{} byte(s) differ", count));
+                                    }
+                                    None => {
+                                        ui.label(egui::RichText::new("unknown")
+                                            .color(egui::Color32::GRAY));
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+
+                    self.show_client_running_warning(ui);
+                    self.show_read_only_warning(ui);
+                    let can_confirm = self.show_downgrade_warning(ui);
+                    ui.add_space(15.0);
+
+                    ui.vertical_centered(|ui| {
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(can_confirm, egui::Button::new("  Confirm  ")).clicked() {
+                                confirmed = true;
+                            }
+                            if ui.button("  Cancel  ").clicked() {
+                                cancelled = true;
+                            }
+                        });
+                    });
+
+                    ui.add_space(5.0);
+                });
+
+            if confirmed {
+                self.copy_preview = None;
+                self.copy_settings();
+            } else if cancelled {
+                self.copy_preview = None;
+            }
+        }
+
+        // Confirmation before creating a settings file for a new character
+        if self.confirm_create {
+            let mut confirmed = false;
+            let mut cancelled = false;
+
+            let source_name = self.copy_from.as_deref()
+                .map(|path| self.path_display_name(path))
+                .unwrap_or_default();
+            let target = self.new_character_target_folder()
+                .map(|folder| folder.join(format!("core_char_{}.dat", self.new_character_id.trim())))
+                .map(|path| path.display().to_string())
+                .unwrap_or_default();
+
+            egui::Window::new("Create Settings File")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.add_space(10.0);
+
+                    ui.label(format!("Create settings for character {} from {}?",
+                        self.new_character_id.trim(), source_name));
+                    ui.label(egui::RichText::new(target)
+                        .small()
+                        .color(egui::Color32::GRAY));
+                    ui.add_space(10.0);
+                    ui.label(egui::RichText::new(
+                        "⚠ Make sure EVE is not running, or it may overwrite the new file when it exits.")
+                        .color(egui::Color32::from_rgb(230, 160, 0)));
+                    ui.add_space(15.0);
+
+                    ui.vertical_centered(|ui| {
+                        ui.horizontal(|ui| {
+                            if ui.button("  Create  ").clicked() {
+                                confirmed = true;
+                            }
+                            if ui.button("  Cancel  ").clicked() {
+                                cancelled = true;
+                            }
+                        });
+                    });
+
+                    ui.add_space(5.0);
+                });
+
+            if confirmed {
+                self.confirm_create = false;
+                self.create_new_character_file();
+            } else if cancelled {
+                self.confirm_create = false;
+            }
+        }
+
+        // Confirmation before deleting a settings file
+        if let Some(path) = self.pending_delete.clone() {
+            let mut confirmed = false;
+            let mut cancelled = false;
+
+            let name = self.settings_files.iter()
+                .find(|f| f.path == path)
+                .map(|f| self.character_display_name(&f.character_id))
+                .unwrap_or_default();
+
+            egui::Window::new("Delete Settings File")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.add_space(10.0);
+
+                    ui.label(format!("Delete the settings file for {}?", name));
+                    ui.label(egui::RichText::new(path.display().to_string())
+                        .small()
+                        .color(egui::Color32::GRAY));
+                    ui.add_space(10.0);
+                    ui.label(egui::RichText::new("This can't be undone.")
+                        .color(egui::Color32::RED));
+                    ui.add_space(15.0);
+
+                    ui.vertical_centered(|ui| {
+                        ui.horizontal(|ui| {
+                            if ui.button("  Delete  ").clicked() {
+                                confirmed = true;
+                            }
+                            if ui.button("  Cancel  ").clicked() {
+                                cancelled = true;
+                            }
+                        });
+                    });
+
+                    ui.add_space(5.0);
+                });
+
+            if confirmed {
+                self.pending_delete = None;
+                self.delete_settings_file(&path);
+            } else if cancelled {
+                self.pending_delete = None;
+            }
+        }
+
+        // Editing one character's note
+        if let Some((character_id, mut text)) = self.editing_note.take() {
+            let mut saved = false;
+            let mut cancelled = false;
+
+            egui::Window::new(format!("Note for {}", self.character_display_name(&character_id)))
+                // The title changes if the name resolves while the editor is open
+                .id(egui::Id::new("note_editor"))
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.add_space(10.0);
+                    ui.add(egui::TextEdit::multiline(&mut text)
+                        .hint_text("e.g. main, hauler, scout")
+                        .desired_rows(3)
+                        .desired_width(300.0));
+                    ui.add_space(15.0);
+
+                    ui.vertical_centered(|ui| {
+                        ui.horizontal(|ui| {
+                            if ui.button("  Save  ").clicked() {
+                                saved = true;
+                            }
+                            if ui.button("  Cancel  ").clicked() {
+                                cancelled = true;
+                            }
+                        });
+                    });
+
+                    ui.add_space(5.0);
+                });
+
+            if saved {
+                let text = text.trim().to_string();
+                if text.is_empty() {
+                    self.notes.remove(&character_id);
+                } else {
+                    self.notes.insert(character_id, text);
+                }
+                self.writer.save("notes.json", &self.notes);
+            } else if !cancelled {
+                self.editing_note = Some((character_id, text));
+            }
+        }
+
+        // Choosing, then confirming, which files to restore from a zip
+        if let Some(mut plan) = self.restore_plan.take() {
+            let mut proceed = false;
+            let mut cancelled = false;
+
+            egui::Window::new("Restore from Zip")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.add_space(10.0);
+
+                    if plan.confirming {
+                        let selected: Vec<&RestoreEntry> = plan.entries.iter()
+                            .filter(|entry| entry.selected)
+                            .collect();
+                        ui.label(format!("{} file(s) will be overwritten:", selected.len()));
+                        ui.add_space(5.0);
+                        for entry in selected {
+                            let target = entry.target.as_ref()
+                                .map(|t| t.display().to_string())
+                                .unwrap_or_else(|| format!("{} (no matching install)", entry.name));
+                            ui.label(format!("  • {}", target));
+                        }
+                        ui.add_space(10.0);
+                        ui.label(egui::RichText::new("Existing files are backed up first.")
+                            .color(egui::Color32::GRAY)
+                            .italics());
+                    } else {
+                        ui.label(format!("Character files in {}:", plan.zip_path.display()));
+                        ui.add_space(5.0);
+
+                        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                            egui::Grid::new("restore_grid")
+                                .striped(true)
+                                .spacing([15.0, 4.0])
+                                .show(ui, |ui| {
+                                    ui.label("");
+                                    ui.label(egui::RichText::new("File").strong());
+                                    ui.label(egui::RichText::new("Restores To").strong());
+                                    ui.end_row();
+
+                                    for entry in &mut plan.entries {
+                                        ui.checkbox(&mut entry.selected, "");
+                                        ui.label(&entry.name);
+                                        let folder_exists = entry.target.as_ref()
+                                            .and_then(|t| t.parent())
+                                            .map(|folder| folder.is_dir())
+                                            .unwrap_or(false);
+                                        match &entry.target {
+                                            Some(target) if folder_exists => {
+                                                ui.label(target.display().to_string());
+                                            }
+                                            Some(target) => {
+                                                ui.label(egui::RichText::new(format!(
+                                                    "⚠ {} (folder missing)", target.display()))
+                                                    .color(egui::Color32::RED));
+                                            }
+                                            None => {
+                                                ui.label(egui::RichText::new("⚠ No matching install")
+                                                    .color(egui::Color32::RED));
+                                            }
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                    }
+
+                    ui.add_space(15.0);
+
+                    let any_selected = plan.entries.iter().any(|entry| entry.selected);
+                    ui.vertical_centered(|ui| {
+                        ui.horizontal(|ui| {
+                            let label = if plan.confirming { "  Confirm  " } else { "  Restore Selected…  " };
+                            if ui.add_enabled(any_selected, egui::Button::new(label)).clicked() {
+                                proceed = true;
+                            }
+                            if ui.button("  Cancel  ").clicked() {
+                                cancelled = true;
+                            }
+                        });
+                    });
+
+                    ui.add_space(5.0);
+                });
+
+            if proceed && plan.confirming {
+                self.restore_selected(plan);
+            } else if proceed {
+                plan.confirming = true;
+                self.restore_plan = Some(plan);
+            } else if !cancelled {
+                self.restore_plan = Some(plan);
+            }
+        }
+
+        // Help > About
+        if self.show_about {
+            egui::Window::new("About ToonSettings")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.add_space(10.0);
+                    ui.vertical_centered(|ui| {
+                        ui.heading("ToonSettings");
+                        ui.label(format!("Version {}", APP_VERSION));
+                        ui.add_space(5.0);
+                        ui.label("Copy EVE Online character settings between characters.");
+                        ui.label(egui::RichText::new("ChrisRid 2025").color(egui::Color32::GRAY));
+                        ui.add_space(15.0);
+                        if ui.button("  OK  ").clicked() {
+                            self.show_about = false;
+                        }
+                    });
+                    ui.add_space(5.0);
+                });
+        }
+
+        // Which file a loaded profile's source should be copied from
+        if let Some(profile) = self.choose_source.clone() {
+            let source_id = profile.source.clone().unwrap_or_default();
+            let mut choices: Vec<&SettingsFile> = self.settings_files.iter()
+                .filter(|f| f.character_id == source_id && !f.invalid)
+                .collect();
+            choices.sort_by_key(|file| std::cmp::Reverse(file.modified));
+            let mut chosen: Option<Option<PathBuf>> = None;
+            egui::Window::new("Choose Source Profile")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.add_space(10.0);
+                    ui.label(format!("{} has settings in {} profile folders. Which one should \"{}\" copy from?",
+                        self.character_display_name(&source_id), choices.len(), profile.name));
+                    ui.add_space(10.0);
+                    egui::Grid::new("source_profiles").striped(true).spacing([12.0, 4.0]).show(ui, |ui| {
+                        ui.strong("Profile");
+                        ui.strong("Last Modified");
+                        ui.label("");
+                        ui.end_row();
+                        for file in &choices {
+                            ui.label(&file.profile).on_hover_text(file.path.display().to_string());
+                            if self.active_files.contains(&file.path) {
+                                ui.label(egui::RichText::new(format!("● {}", self.config.time_display.format(file.modified)))
+                                    .color(success_color(ui)))
+                                    .on_hover_text("The newest of these, so most likely the one EVE uses");
+                            } else {
+                                ui.label(self.config.time_display.format(file.modified));
+                            }
+                            if ui.button("Use this").clicked() {
+                                chosen = Some(Some(file.path.clone()));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                    ui.add_space(10.0);
+                    ui.vertical_centered(|ui| {
+                        if ui.button("  Cancel  ").clicked() {
+                            chosen = Some(None);
+                        }
+                    });
+                    ui.add_space(5.0);
+                });
+            match chosen {
+                Some(Some(path)) => {
+                    self.choose_source = None;
+                    self.apply_copy_profile(profile, Some(path));
+                }
+                Some(None) => self.choose_source = None,
+                None => {}
+            }
+        }
+
+        // File > Copy History
+        if self.show_history {
+            let mut reselect = None;
+            let mut close = false;
+            egui::Window::new("Copy History")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    if self.history.is_empty() {
+                        ui.label("No copies yet.");
+                    } else {
+                        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                            egui::Grid::new("copy_history").striped(true).spacing([12.0, 4.0]).show(ui, |ui| {
+                                ui.strong("Time (UTC)");
+                                ui.strong("By");
+                                ui.strong("From");
+                                ui.strong("To");
+                                ui.label("");
+                                ui.end_row();
+                                for (i, entry) in self.history.iter().enumerate() {
+                                    ui.label(&entry.time);
+                                    ui.label(&entry.user);
+                                    ui.label(&entry.source_name)
+                                        .on_hover_text(entry.source.display().to_string());
+                                    let names: Vec<&str> = entry.destinations.iter()
+                                        .map(|(_, name)| name.as_str())
+                                        .collect();
+                                    ui.label(format!("{} character(s)", names.len()))
+                                        .on_hover_text(names.join("\n"));
+                                    if ui.button("Select")
+                                        .on_hover_text("Select the same source and destinations again")
+                                        .clicked()
+                                    {
+                                        reselect = Some(i);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                    }
+                    ui.add_space(10.0);
+                    ui.vertical_centered(|ui| {
+                        if ui.button("  Close  ").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            if let Some(index) = reselect {
+                self.reselect_history(index);
+            } else if close {
+                self.show_history = false;
+            }
+        }
+
+        // Source against its single destination
+        if let Some(diff) = &self.settings_diff {
+            let mut close = false;
+            egui::Window::new("Diff")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label(egui::RichText::new(format!("{} → {}", diff.source_name, diff.dest_name)).strong());
+                    ui.label(format!("Sizes: {} / {} bytes", diff.source_size, diff.dest_size));
+                    if diff.differing_bytes == 0 {
+                        ui.colored_label(success_color(ui), "The files are identical.");
+                    } else {
+                        ui.label(format!("{} byte(s) differ in {} region(s)",
+                            diff.differing_bytes, diff.regions.len() + diff.more_regions));
+
+                        ui.add_space(8.0);
+                        ui.strong("Readable text");
+                        if diff.only_in_source.is_empty() && diff.only_in_dest.is_empty() {
+                            ui.label(egui::RichText::new("Same text in both files").color(egui::Color32::GRAY));
+                        }
+                        for (label, strings) in [
+                            (format!("Only in {}:", diff.source_name), &diff.only_in_source),
+                            (format!("Only in {}:", diff.dest_name), &diff.only_in_dest),
+                        ] {
+                            if strings.is_empty() {
+                                continue;
+                            }
+                            ui.label(format!("{} ({})", label, strings.len()));
+                            egui::ScrollArea::vertical()
+                                .id_salt(("diff_text", label.as_str()))
+                                .max_height(80.0)
+                                .show(ui, |ui| {
+                                    ui.label(egui::RichText::new(strings.join(", ")).monospace());
+                                });
+                        }
+
+                        ui.add_space(8.0);
+                        ui.strong("Differing regions");
+                        egui::ScrollArea::vertical().id_salt("diff_regions").max_height(250.0).show(ui, |ui| {
+                            egui::Grid::new("diff_regions_grid").striped(true).spacing([12.0, 2.0]).show(ui, |ui| {
+                                ui.strong("Offset");
+                                ui.strong("Length");
+                                ui.strong("Source");
+                                ui.strong("Destination");
+                                ui.end_row();
+                                for region in &diff.regions {
+                                    ui.monospace(format!("0x{:06X}", region.offset));
+                                    ui.label(region.len.to_string());
+                                    ui.monospace(&region.source_preview);
+                                    ui.monospace(&region.dest_preview);
+                                    ui.end_row();
+                                }
+                            });
+                            if diff.more_regions > 0 {
+                                ui.label(format!("…and {} more", diff.more_regions));
+                            }
+                        });
+                    }
+                    ui.add_space(10.0);
+                    ui.vertical_centered(|ui| {
+                        if ui.button("  Close  ").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            if close {
+                self.settings_diff = None;
+            }
+        }
 
         // Popup dialog for copy status
         if self.show_popup {
-            egui::Window::new("Copy Status")
+            egui::Window::new(&self.popup_title)
                 .collapsible(false)
                 .resizable(false)
                 .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
@@ -343,7 +5226,7 @@ impl eframe::App for EveSettingsApp {
                     
                     let icon = if self.popup_success { "✓" } else { "⚠" };
                     let color = if self.popup_success {
-                        egui::Color32::from_rgb(0, 200, 0)
+                        success_color(ui)
                     } else {
                         egui::Color32::RED
                     };
@@ -352,12 +5235,45 @@ impl eframe::App for EveSettingsApp {
                         ui.label(egui::RichText::new(icon).color(color));
                         ui.label(&self.popup_message);
                     });
+
+                    if !self.popup_rows.is_empty() {
+                        ui.add_space(10.0);
+                        egui::ScrollArea::vertical()
+                            .max_height(300.0)
+                            .show(ui, |ui| {
+                                egui::Grid::new("popup_rows")
+                                    .striped(true)
+                                    .spacing([15.0, 4.0])
+                                    .show(ui, |ui| {
+                                        ui.label(egui::RichText::new("Character").strong());
+                                        ui.label(egui::RichText::new("File").strong());
+                                        ui.label(egui::RichText::new("Result").strong());
+                                        ui.end_row();
+                                        for row in &self.popup_rows {
+                                            ui.label(&row.name);
+                                            ui.label(egui::RichText::new(&row.path)
+                                                .color(egui::Color32::GRAY)
+                                                .small());
+                                            match &row.result {
+                                                Ok(()) if row.identical => ui.label(egui::RichText::new("Already identical")
+                                                    .color(egui::Color32::GRAY)),
+                                                Ok(()) => ui.label(egui::RichText::new("OK")
+                                                    .color(success_color(ui))),
+                                                Err(e) => ui.label(egui::RichText::new(format!("FAILED: {}", e))
+                                                    .color(egui::Color32::RED)),
+                                            };
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                    }
                     
                     ui.add_space(15.0);
                     
                     ui.vertical_centered(|ui| {
                         if ui.button("  OK  ").clicked() {
                             self.show_popup = false;
+                            self.popup_rows.clear();
                         }
                     });
                     
@@ -365,18 +5281,86 @@ impl eframe::App for EveSettingsApp {
                 });
         }
 
+        // Ahead of the central panel so it takes the top edge of the window
+        self.show_menu_bar(ctx);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.add_space(10.0);
             
-            // Header - centered
-            ui.vertical_centered(|ui| {
+            // Header - centered, with the theme toggle tucked in the top-right corner
+            let header_rect = ui.vertical_centered(|ui| {
                 ui.heading("ToonSettings");
-            });
+            }).response.rect;
+            let (theme_icon, theme_hint) = match self.config.theme {
+                Theme::Dark => ("☀", "Switch to light mode"),
+                Theme::Light => ("🌙", "Switch to dark mode"),
+            };
+            let toggle_rect = egui::Rect::from_min_size(
+                egui::pos2(header_rect.right() - 28.0, header_rect.top()),
+                egui::vec2(28.0, header_rect.height()),
+            );
+            // Lookups still running in the background
+            if has_loading {
+                let spinner_rect = toggle_rect.translate(egui::vec2(-toggle_rect.width(), 0.0));
+                ui.put(spinner_rect, egui::Spinner::new())
+                    .on_hover_text("Looking up names and portraits");
+            }
+            if ui.put(toggle_rect, egui::Button::new(theme_icon)).on_hover_text(theme_hint).clicked() {
+                self.config.theme = match self.config.theme {
+                    Theme::Dark => Theme::Light,
+                    Theme::Light => Theme::Dark,
+                };
+                self.save_config();
+            }
             
             ui.add_space(5.0);
             ui.separator();
             ui.add_space(10.0);
 
+            // Game server status, fetched at scan time
+            let server_offline = matches!(self.server_status, Some(ServerStatus::Offline(_)));
+            match &self.server_status {
+                Some(ServerStatus::Online { players }) => {
+                    ui.label(egui::RichText::new(format!("● {} online - {} players",
+                        self.config.datasource.label(), players))
+                        .color(success_color(ui)));
+                    ui.add_space(5.0);
+                }
+                Some(ServerStatus::Offline(reason)) => {
+                    ui.label(egui::RichText::new(format!(
+                        "⚠ {} appears to be offline ({}). Character names can't be resolved until it is back.",
+                        self.config.datasource.label(), reason))
+                        .color(egui::Color32::from_rgb(230, 160, 0)));
+                    ui.add_space(5.0);
+                }
+                None if self.config.offline && !self.settings_files.is_empty() => {
+                    ui.label(egui::RichText::new("Offline mode - no ESI lookups are made")
+                        .color(egui::Color32::GRAY));
+                    ui.add_space(5.0);
+                }
+                None => {}
+            }
+
+            // Name lookup progress, only while ESI lookups are outstanding
+            let (resolved, total) = self.name_lookup_progress();
+            if resolved < total {
+                ui.horizontal(|ui| {
+                    if ui.button("Cancel").clicked() {
+                        self.cancel_lookups();
+                    }
+                    let progress = egui::ProgressBar::new(resolved as f32 / total as f32);
+                    let progress = if server_offline {
+                        progress
+                            .fill(egui::Color32::GRAY)
+                            .text(format!("Waiting for the server: {}/{} names resolved", resolved, total))
+                    } else {
+                        progress.text(format!("Resolving character names: {}/{}", resolved, total))
+                    };
+                    ui.add(progress);
+                });
+                ui.add_space(10.0);
+            }
+
             // Path input and scan button
             ui.horizontal(|ui| {
                 ui.label("Settings Path:");
@@ -385,18 +5369,184 @@ impl eframe::App for EveSettingsApp {
                         .desired_width(500.0)
                 );
                 if response.changed() {
-                    self.scan_complete = false;
+                    self.scan_complete = false;
+                }
+                if ui.button("📁 Browse…").clicked() {
+                    self.browse_for_path();
+                }
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    self.rescan();
+                }
+                if ui.button("🔍 Scan").on_hover_text("Ctrl+R / F5").clicked() {
+                    self.rescan();
+                }
+                if ui.checkbox(&mut self.config.auto_rescan, "Auto-rescan")
+                    .on_hover_text("Scan again by itself when EVE writes or adds a settings file")
+                    .changed()
+                {
+                    self.save_config();
+                }
+            });
+
+            // Additional installations scanned alongside the main path
+            let mut remove_install: Option<usize> = None;
+            for (i, extra) in self.config.additional_paths.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label("Also scanning:");
+                    ui.label(extra);
+                    if ui.small_button("✖").on_hover_text("Stop scanning this path").clicked() {
+                        remove_install = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = remove_install {
+                self.config.additional_paths.remove(i);
+                self.save_config();
+                self.scan_complete = false;
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Add Install:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.new_install_path)
+                        .hint_text("Another EVE settings path, e.g. a standalone client")
+                        .desired_width(420.0)
+                );
+                if ui.button("📁").on_hover_text("Browse for another install").clicked() {
+                    let start_dir = self.eve_path.clone();
+                    self.open_file_dialog(|| pick_folder(FileDialogPurpose::AddInstall, &start_dir));
+                }
+                let new_path = self.new_install_path.trim().to_string();
+                let can_add = !new_path.is_empty()
+                    && new_path != self.eve_path
+                    && !self.config.additional_paths.contains(&new_path);
+                if ui.add_enabled(can_add, egui::Button::new("➕ Add")).clicked() {
+                    self.config.additional_paths.push(new_path);
+                    self.new_install_path.clear();
+                    self.save_config();
+                    self.scan_complete = false;
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Names File:");
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.config.names_file)
+                        .hint_text("Optional names.json of character ID to name, used instead of ESI")
+                        .desired_width(420.0)
+                );
+                // Saved once editing is done (Enter or clicking away), not on every key
+                if response.lost_focus() {
+                    self.save_config();
+                    if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        self.scan_complete = false;
+                    }
+                }
+                if ui.button("📁").on_hover_text("Browse for a names file").clicked() {
+                    self.open_file_dialog(pick_names_file);
+                }
+                if let Some(error) = &self.roster_error {
+                    ui.colored_label(egui::Color32::RED, format!("⚠ {}", error));
+                } else if !self.roster_names.is_empty() {
+                    ui.label(egui::RichText::new(format!("{} name(s); rescan after editing it", self.roster_names.len()))
+                        .color(egui::Color32::GRAY));
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Server:");
+                let mut datasource = self.config.datasource;
+                egui::ComboBox::from_id_salt("datasource")
+                    .selected_text(datasource.label())
+                    .show_ui(ui, |ui| {
+                        for option in Datasource::ALL {
+                            ui.selectable_value(&mut datasource, option, option.label());
+                        }
+                    });
+                if datasource != self.config.datasource {
+                    self.set_datasource(datasource);
+                }
+
+                ui.add_space(20.0);
+                let response = ui.checkbox(&mut self.config.offline, "Offline")
+                    .on_hover_text("Don't contact ESI at all; names show as \"(offline)\" and copying works as usual");
+                if response.changed() {
+                    self.save_config();
+                    self.scan_complete = false;
+                }
+
+                ui.add_space(20.0);
+                let expired = if self.config.offline { 0 } else { self.expired_name_ids().len() };
+                if ui.add_enabled(expired > 0, egui::Button::new(format!("Refresh expired ({})", expired)))
+                    .on_hover_text("Look up again, in full, only the names whose ESI cache time has passed; scans refresh them too")
+                    .on_disabled_hover_text("No cached names have expired")
+                    .clicked()
+                {
+                    self.refresh_expired_names();
+                }
+
+                ui.add_space(20.0);
+                ui.label("Max concurrent requests:");
+                let response = ui.add(egui::DragValue::new(&mut self.config.max_concurrent_requests)
+                    .range(CONCURRENT_REQUESTS_RANGE))
+                    .on_hover_text("ESI lookups run in parallel up to this limit; takes effect on the next scan");
+                if response.changed() {
+                    self.save_config();
+                }
+
+                ui.add_space(20.0);
+                ui.label("Timeout:");
+                let response = ui.add(egui::DragValue::new(&mut self.config.request_timeout_secs)
+                    .range(REQUEST_TIMEOUT_RANGE)
+                    .suffix(" s"))
+                    .on_hover_text("How long to wait for ESI before giving up on a request");
+                if response.changed() {
+                    self.save_config();
+                }
+
+                ui.add_space(20.0);
+                ui.label("Delay:");
+                let response = ui.add(egui::DragValue::new(&mut self.config.request_delay_ms)
+                    .range(REQUEST_DELAY_RANGE)
+                    .speed(10)
+                    .suffix(" ms"))
+                    .on_hover_text("Pause after each request before its slot is reused; raise it to go easier on ESI and your connection");
+                if response.changed() {
+                    self.save_config();
                 }
-                if ui.button("🔍 Scan").clicked() {
+
+                ui.add_space(20.0);
+                ui.label("Scan depth:");
+                let response = ui.add(egui::DragValue::new(&mut self.config.scan_depth)
+                    .range(SCAN_DEPTH_RANGE))
+                    .on_hover_text("How many folders below the settings path to look for settings_* folders");
+                if response.changed() {
+                    self.save_config();
                     self.scan_complete = false;
-                    self.settings_files.clear();
-                    self.character_names.clear();
-                    self.copy_from = None;
-                    self.copy_to.clear();
-                    self.scan_files();
+                }
+
+                ui.add_space(20.0);
+                ui.label("ESI URL:");
+                let response = ui.add(egui::TextEdit::singleline(&mut self.config.esi_base_url)
+                    .hint_text(DEFAULT_ESI_BASE_URL)
+                    .desired_width(200.0))
+                    .on_hover_text("Send ESI requests to a mirror or proxy instead; leave blank for the official server. Takes effect on the next scan");
+                if response.lost_focus() {
+                    self.save_config();
+                }
+                if !self.config.esi_base_url.trim().is_empty() && normalize_esi_base_url(&self.config.esi_base_url).is_none() {
+                    ui.colored_label(egui::Color32::RED, "⚠")
+                        .on_hover_text(format!("Not a valid http(s) URL - using {} instead", DEFAULT_ESI_BASE_URL));
                 }
             });
 
+            if let Some(notice) = &self.path_notice {
+                ui.add_space(5.0);
+                ui.label(egui::RichText::new(notice)
+                    .color(egui::Color32::GRAY)
+                    .italics());
+            }
+
             ui.add_space(15.0);
 
             // Error message if any
@@ -410,32 +5560,309 @@ impl eframe::App for EveSettingsApp {
                 ui.add_space(10.0);
             }
 
+            // Permission problems don't stop the scan but can hide characters
+            if !self.scan_warnings.is_empty() {
+                let warning_color = egui::Color32::from_rgb(230, 160, 0);
+                ui.label(egui::RichText::new(format!(
+                    "⚠ Couldn't read {} folder(s) - characters in them are missing from the list:",
+                    self.scan_warnings.len()))
+                    .color(warning_color));
+                for warning in &self.scan_warnings {
+                    ui.label(egui::RichText::new(format!("    {}", warning))
+                        .color(warning_color)
+                        .small());
+                }
+                ui.add_space(10.0);
+            }
+
             // Auto-scan on first run
             if !self.scan_complete {
                 self.scan_files();
             }
+            if self.scan_job.is_some() {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(egui::RichText::new("Scanning…").color(egui::Color32::GRAY));
+                });
+                ui.add_space(10.0);
+            }
 
             // Results section
             if !self.settings_files.is_empty() {
-                ui.label(format!("Found {} character settings files:", self.settings_files.len()));
+                // Sizes were read along with the timestamps during the scan
+                let total_size: u64 = self.settings_files.iter().filter_map(|f| f.size).sum();
+                ui.label(format!("Found {} character settings files ({}):",
+                    self.settings_files.len(), format_size_short(total_size)));
                 
                 ui.add_space(10.0);
 
+                // Search filter
+                ui.horizontal(|ui| {
+                    ui.add_space(10.0);
+                    ui.label("Search:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.search_query)
+                            .hint_text("Name or character ID")
+                            .desired_width(250.0)
+                    );
+                    if !self.search_query.is_empty() && ui.small_button("✖").on_hover_text("Clear search").clicked() {
+                        self.search_query.clear();
+                    }
+
+                    ui.add_space(20.0);
+                    ui.checkbox(&mut self.hide_unresolved, "Hide unresolved")
+                        .on_hover_text("Hide characters whose name is still loading");
+                    ui.add_enabled(self.hide_unresolved, egui::Checkbox::new(&mut self.hide_errors, "Including errors"))
+                        .on_hover_text("Also hide characters whose lookup failed");
+
+                    ui.add_space(20.0);
+                    ui.selectable_value(&mut self.group_by_corporation, false, "Flat");
+                    ui.selectable_value(&mut self.group_by_corporation, true, "Grouped by corporation");
+                });
+
+                ui.add_space(5.0);
+
+                // Source selection; only characters with a usable file are offered
+                ui.horizontal(|ui| {
+                    ui.add_space(10.0);
+                    ui.label("Copy From:");
+
+                    // One entry per file, so a character in several profiles is listed once for each
+                    let mut sources: Vec<(PathBuf, String)> = self.settings_files.iter()
+                        .filter(|f| !f.invalid)
+                        .map(|f| {
+                            let name = self.path_display_name(&f.path);
+                            let mut label = match f.character_name {
+                                CharacterNameStatus::Found(_) => format!("{} ({})", name, f.character_id),
+                                _ => name,
+                            };
+                            // Tell the profiles apart by when EVE last wrote each
+                            if self.settings_files.iter().filter(|other| other.character_id == f.character_id).count() > 1 {
+                                label.push_str(&format!(" - {}", format_timestamp(f.modified)));
+                            }
+                            (f.path.clone(), label)
+                        })
+                        .collect();
+                    sources.sort_by(|a, b| a.1.to_lowercase().cmp(&b.1.to_lowercase())
+                        .then_with(|| a.0.cmp(&b.0)));
+
+                    let selected_text = self.copy_from.as_ref()
+                        .and_then(|path| sources.iter().find(|(source, _)| source == path))
+                        .map(|(_, label)| label.clone())
+                        .unwrap_or_else(|| "Choose a source…".to_string());
+                    let mut new_copy_from = self.copy_from.clone();
+                    egui::ComboBox::from_id_salt("copy_from")
+                        .width(300.0)
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut new_copy_from, None, "None");
+                            for (path, label) in &sources {
+                                ui.selectable_value(&mut new_copy_from, Some(path.clone()), label);
+                            }
+                        });
+                    if new_copy_from != self.copy_from {
+                        // The source can't also be a destination
+                        if let Some(path) = &new_copy_from {
+                            self.copy_to.remove(path);
+                        }
+                        self.copy_from = new_copy_from;
+                    }
+                });
+
+                ui.add_space(5.0);
+
+                // Saved selections
+                ui.horizontal(|ui| {
+                    ui.add_space(10.0);
+                    ui.label("Profile:");
+
+                    let mut load_profile: Option<String> = None;
+                    egui::ComboBox::from_id_salt("copy_profile")
+                        .width(180.0)
+                        .selected_text("Load a profile…")
+                        .show_ui(ui, |ui| {
+                            if self.config.copy_profiles.is_empty() {
+                                ui.label(egui::RichText::new("No saved profiles")
+                                    .color(egui::Color32::GRAY)
+                                    .italics());
+                            }
+                            for profile in &self.config.copy_profiles {
+                                let label = format!("{} ({} destination(s))", profile.name, profile.destinations.len());
+                                if ui.selectable_label(false, label).clicked() {
+                                    load_profile = Some(profile.name.clone());
+                                }
+                            }
+                        });
+                    if let Some(name) = load_profile {
+                        self.load_copy_profile(&name);
+                    }
+
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.profile_name)
+                            .hint_text("Profile name")
+                            .desired_width(150.0)
+                    );
+                    let name = self.profile_name.trim().to_string();
+                    let exists = self.config.copy_profiles.iter().any(|p| p.name == name);
+                    let save_label = if exists { "💾 Update" } else { "💾 Save" };
+                    if ui.add_enabled(!name.is_empty() && self.can_copy(), egui::Button::new(save_label))
+                        .on_hover_text("Save the current source and destinations under this name")
+                        .clicked()
+                    {
+                        self.save_copy_profile(name.clone());
+                    }
+                    if exists && ui.button("🗑 Delete").clicked() {
+                        self.config.copy_profiles.retain(|p| p.name != name);
+                        self.save_config();
+                    }
+                });
+
+                ui.add_space(5.0);
+
+                // Bulk destination selection
+                ui.horizontal(|ui| {
+                    ui.add_space(10.0);
+                    ui.label("Copy To:");
+                    if ui.button("Select All").clicked() {
+                        self.select_all_destinations();
+                    }
+                    if ui.button("Clear All").clicked() {
+                        self.clear_all_destinations();
+                    }
+                    if ui.button("Invert")
+                        .on_hover_text("Tick the shown characters that aren't ticked, and untick the ones that are")
+                        .clicked()
+                    {
+                        self.invert_destinations();
+                    }
+                    let same_corp_hint = if self.copy_from.is_none() {
+                        "Choose a source first"
+                    } else {
+                        "Wait for the source's corporation to be looked up"
+                    };
+                    if ui.add_enabled(self.source_corporation().is_some(), egui::Button::new("Select same-corp"))
+                        .on_hover_text("Tick every other character in the source's corporation")
+                        .on_disabled_hover_text(same_corp_hint)
+                        .clicked()
+                    {
+                        self.select_same_corporation();
+                    }
+                    if ui.add_enabled(self.copy_from.is_some(), egui::Button::new("Everyone else"))
+                        .on_hover_text("Replace the destinations with every other shown character, leaving out files too small to be valid")
+                        .on_disabled_hover_text("Choose a source first")
+                        .clicked()
+                    {
+                        self.select_everyone_else();
+                    }
+                });
+
+                // Seeding a character that has never logged in, so has no file yet
+                ui.horizontal(|ui| {
+                    ui.add_space(10.0);
+                    ui.label("New character:");
+                    ui.add(egui::TextEdit::singleline(&mut self.new_character_id)
+                        .hint_text("Character ID")
+                        .desired_width(120.0));
+
+                    let folders = self.profile_folders();
+                    let target = self.new_character_target_folder();
+                    let selected_label = target.as_ref()
+                        .and_then(|target| folders.iter().find(|(folder, _)| folder == target))
+                        .map(|(_, label)| label.clone())
+                        .unwrap_or_else(|| "(choose a folder)".to_string());
+                    ui.label("in");
+                    egui::ComboBox::from_id_salt("new_character_folder")
+                        .selected_text(selected_label)
+                        .show_ui(ui, |ui| {
+                            for (folder, label) in &folders {
+                                if ui.selectable_label(target.as_ref() == Some(folder), label)
+                                    .on_hover_text(folder.display().to_string())
+                                    .clicked()
+                                {
+                                    self.new_character_folder = Some(folder.clone());
+                                }
+                            }
+                        });
+
+                    let id = self.new_character_id.trim();
+                    let valid_id = !id.is_empty() && id.chars().all(|c| c.is_ascii_digit());
+                    let hint = if self.copy_from.is_none() {
+                        "Choose a source first"
+                    } else {
+                        "Enter the new character's numeric ID"
+                    };
+                    if ui.add_enabled(self.copy_from.is_some() && valid_id && target.is_some(),
+                            egui::Button::new("Create from source"))
+                        .on_hover_text("Write a settings file for this character from the source's")
+                        .on_disabled_hover_text(hint)
+                        .clicked()
+                    {
+                        self.confirm_create = true;
+                    }
+                });
+
+                ui.add_space(10.0);
+
+                // Corporation spread across the scanned characters
+                if let Some(summary) = self.corporation_summary() {
+                    ui.horizontal(|ui| {
+                        ui.add_space(10.0);
+                        ui.label(egui::RichText::new(summary).color(egui::Color32::GRAY));
+                    });
+                    ui.add_space(5.0);
+                }
+
+                // Only worth a column once more than one install is being scanned
+                let show_install = !self.config.additional_paths.is_empty();
+
                 // Column headers
                 ui.horizontal(|ui| {
                     ui.add_space(10.0);
-                    ui.add_sized([200.0, 20.0], egui::Label::new(
-                        egui::RichText::new("Filename").strong()
-                    ));
-                    ui.add_sized([120.0, 20.0], egui::Label::new(
-                        egui::RichText::new("Character ID").strong()
+                    // Pin and portrait columns have no header
+                    ui.add_sized([20.0, 20.0], egui::Label::new(""));
+                    ui.add_space(self.config.density.portrait_size());
+                    let mut clicked_sort: Option<SortKey> = None;
+                    for (key, title, width) in [
+                        (SortKey::Filename, "Filename", 200.0),
+                        (SortKey::CharacterId, "Character ID", 120.0),
+                        (SortKey::CharacterName, "Character Name", 150.0),
+                    ] {
+                        let text = if self.sort_key == key {
+                            let arrow = if self.sort_ascending { "▲" } else { "▼" };
+                            format!("{} {}", title, arrow)
+                        } else {
+                            title.to_string()
+                        };
+                        let header = ui.add_sized([width, 20.0], egui::Label::new(
+                            egui::RichText::new(text).strong()
+                        ).sense(egui::Sense::click()));
+                        if header.on_hover_text("Click to sort").clicked() {
+                            clicked_sort = Some(key);
+                        }
+                    }
+                    if let Some(key) = clicked_sort {
+                        self.toggle_sort(key);
+                    }
+                    if show_install {
+                        ui.add_sized([110.0, 20.0], egui::Label::new(
+                            egui::RichText::new("Install").strong()
+                        ));
+                    }
+                    ui.add_sized([150.0, 20.0], egui::Label::new(
+                        egui::RichText::new("Corporation").strong()
                     ));
                     ui.add_sized([150.0, 20.0], egui::Label::new(
-                        egui::RichText::new("Character Name").strong()
+                        egui::RichText::new("Alliance").strong()
                     ));
-                    ui.add_sized([70.0, 20.0], egui::Label::new(
-                        egui::RichText::new("Copy From").strong()
+                    ui.add_sized([90.0, 20.0], egui::Label::new(
+                        egui::RichText::new("Character Age").strong()
                     ));
+                    ui.add_sized([60.0, 20.0], egui::Label::new(
+                        egui::RichText::new("Sec").strong()
+                    )).on_hover_text("Security status; red when negative");
+                    ui.add_sized([130.0, 20.0], egui::Label::new(
+                        egui::RichText::new("Last Modified").strong()
+                    )).on_hover_text("EVE time (UTC)");
                     ui.add_sized([60.0, 20.0], egui::Label::new(
                         egui::RichText::new("Copy To").strong()
                     ));
@@ -445,97 +5872,59 @@ impl eframe::App for EveSettingsApp {
                 ui.separator();
                 ui.add_space(5.0);
 
-                // Scrollable file list
-                egui::ScrollArea::vertical()
-                    .max_height(ui.available_height() - 80.0)
-                    .show(ui, |ui| {
-                    
-                    // Collect changes to apply after iteration
-                    let mut new_copy_from: Option<Option<String>> = None;
-                    let mut copy_to_add: Option<String> = None;
-                    let mut copy_to_remove: Option<String> = None;
-
-                    for file in &self.settings_files {
-                        let char_id = file.character_id.clone();
-                        let is_copy_from = self.copy_from.as_ref() == Some(&char_id);
-                        let is_copy_to = self.copy_to.contains(&char_id);
+                // Filtering only hides rows; settings_files is untouched
+                let visible_files: Vec<usize> = self.settings_files.iter()
+                    .enumerate()
+                    .filter(|(_, file)| self.matches_filter(file))
+                    .map(|(index, _)| index)
+                    .collect();
+                // Portrait (or cell, if taller) height plus the gap added after each row
+                let density = self.config.density;
+                let row_height = density.portrait_size().max(20.0) + density.row_gap() + ui.spacing().item_spacing.y;
 
-                        ui.horizontal(|ui| {
-                            ui.add_space(10.0);
-                            
-                            // Filename
-                            ui.add_sized([200.0, 20.0], egui::Label::new(&file.filename));
-                            
-                            // Character ID
-                            ui.add_sized([120.0, 20.0], egui::Label::new(&file.character_id));
-                            
-                            // Character name with status
-                            let name_text = match &file.character_name {
-                                CharacterNameStatus::Loading => {
-                                    egui::RichText::new("Loading...")
-                                        .color(egui::Color32::GRAY)
-                                        .italics()
-                                }
-                                CharacterNameStatus::Found(name) => {
-                                    egui::RichText::new(name)
-                                        .color(egui::Color32::from_rgb(100, 200, 100))
-                                }
-                                CharacterNameStatus::Error(err) => {
-                                    egui::RichText::new(format!("✗ {}", err))
-                                        .color(egui::Color32::RED)
-                                }
-                            };
-                            ui.add_sized([150.0, 20.0], egui::Label::new(name_text));
-                            
-                            // Copy From checkbox (radio-button behavior - only one can be selected)
-                            let mut from_checked = is_copy_from;
-                            ui.add_sized([70.0, 20.0], |ui: &mut egui::Ui| {
-                                let checkbox = ui.checkbox(&mut from_checked, "");
-                                if checkbox.changed() {
-                                    if from_checked {
-                                        new_copy_from = Some(Some(char_id.clone()));
-                                        // If this was in copy_to, remove it
-                                        if is_copy_to {
-                                            copy_to_remove = Some(char_id.clone());
-                                        }
-                                    } else {
-                                        new_copy_from = Some(None);
-                                    }
-                                }
-                                checkbox
-                            });
-                            
-                            // Copy To checkbox (disabled if this is the copy_from source)
-                            let mut to_checked = is_copy_to;
-                            ui.add_sized([60.0, 20.0], |ui: &mut egui::Ui| {
-                                ui.add_enabled_ui(!is_copy_from, |ui| {
-                                    let checkbox = ui.checkbox(&mut to_checked, "");
-                                    if checkbox.changed() {
-                                        if to_checked {
-                                            copy_to_add = Some(char_id.clone());
-                                        } else {
-                                            copy_to_remove = Some(char_id.clone());
+                // Collect clicks to apply once the list has been drawn
+                let mut row_actions = RowActions::default();
+                let mut display_order = visible_files.clone();
+                if self.group_by_corporation {
+                    let groups = self.corporation_groups(&visible_files);
+                    display_order = groups.iter().flat_map(|(_, _, members)| members.iter().copied()).collect();
+                    egui::ScrollArea::vertical()
+                        .max_height(ui.available_height() - 80.0)
+                        .show(ui, |ui| {
+                            for (corp_id, corp_name, members) in groups {
+                                egui::CollapsingHeader::new(format!("{} ({})", corp_name, members.len()))
+                                    .id_salt(("corp_group", corp_id))
+                                    .default_open(true)
+                                    .show(ui, |ui| {
+                                        for index in members {
+                                            self.show_file_row(ui, &self.settings_files[index],
+                                                show_install, server_offline, &mut row_actions);
                                         }
-                                    }
-                                });
-                                ui.response()
-                            });
+                                    });
+                            }
                         });
-                        
-                        ui.add_space(4.0);
-                    }
-
-                    // Apply changes after iteration
-                    if let Some(new_from) = new_copy_from {
-                        self.copy_from = new_from;
-                    }
-                    if let Some(id) = copy_to_add {
-                        self.copy_to.insert(id);
-                    }
-                    if let Some(id) = copy_to_remove {
-                        self.copy_to.remove(&id);
-                    }
-                });
+                } else if !self.file_details.is_empty() {
+                    // Expanded rows are taller than the rest, so every row is laid out
+                    egui::ScrollArea::vertical()
+                        .max_height(ui.available_height() - 80.0)
+                        .show(ui, |ui| {
+                            for &index in &visible_files {
+                                self.show_file_row(ui, &self.settings_files[index],
+                                    show_install, server_offline, &mut row_actions);
+                            }
+                        });
+                } else {
+                    // Only the rows in view are laid out, which keeps big lists smooth
+                    egui::ScrollArea::vertical()
+                        .max_height(ui.available_height() - 80.0)
+                        .show_rows(ui, row_height, visible_files.len(), |ui, row_range| {
+                            for &index in &visible_files[row_range] {
+                                self.show_file_row(ui, &self.settings_files[index],
+                                    show_install, server_offline, &mut row_actions);
+                            }
+                        });
+                }
+                self.apply_row_actions(row_actions, &display_order);
 
                 ui.add_space(15.0);
 
@@ -544,26 +5933,62 @@ impl eframe::App for EveSettingsApp {
                 
                 ui.vertical_centered(|ui| {
                     ui.horizontal(|ui| {
+                        let copy_hint = if self.copy_from.is_some() && !self.copy_to.is_empty() {
+                            "Waiting for the selected characters' names"
+                        } else {
+                            "Choose a source and at least one destination"
+                        };
                         ui.add_enabled_ui(can_copy, |ui| {
-                            if ui.add_sized([150.0, 35.0], egui::Button::new("📋 Copy Settings")).clicked() {
-                                self.copy_settings();
+                            if ui.add_sized([150.0, 35.0], egui::Button::new("📋 Copy Settings"))
+                                .on_hover_text("Ctrl+Enter")
+                                .on_disabled_hover_text(copy_hint)
+                                .clicked()
+                            {
+                                self.request_copy();
                             }
                         });
 
+                        let swap_broken = self.copy_from.iter().chain(self.copy_to.iter())
+                            .any(|path| self.file_at(path).is_some_and(|f| f.invalid));
+                        if ui.add_enabled(!swap_broken, egui::Button::new("🔄 Swap").min_size(egui::vec2(110.0, 35.0)))
+                            .on_hover_text("Exchange settings between exactly two selected characters")
+                            .on_disabled_hover_text("A selected file is empty or truncated")
+                            .clicked()
+                        {
+                            self.request_swap();
+                        }
+
+                        let can_diff = self.copy_from.is_some() && self.copy_to.len() == 1;
+                        if ui.add_enabled(can_diff, egui::Button::new("🔍 Diff"))
+                            .on_hover_text("Show where the source and destination files differ")
+                            .on_disabled_hover_text("Choose a source and exactly one destination")
+                            .clicked()
+                        {
+                            self.open_diff();
+                        }
+
+                        ui.checkbox(&mut self.preview_mode, "Preview")
+                            .on_hover_text("Show which files would be overwritten before copying");
+                        ui.checkbox(&mut self.copy_options.include_account_settings, "Include account settings")
+                            .on_hover_text("Also copy the matching core_user_*.dat from the same profile folder");
+                        ui.checkbox(&mut self.copy_options.preserve_modified, "Keep modified time")
+                            .on_hover_text("Give copies the source's last-modified time instead of the time of copying");
+                        ui.checkbox(&mut self.copy_options.verify_after_copy, "Verify after copy")
+                            .on_hover_text("Read each copy back and check it matches the source byte for byte");
+                        ui.checkbox(&mut self.copy_options.skip_identical, "Skip identical")
+                            .on_hover_text("Don't back up or rewrite destinations that already match the source");
+                        if ui.checkbox(&mut self.config.wait_for_names, "Wait for names")
+                            .on_hover_text("Only allow copying once the selected characters' names have loaded")
+                            .changed()
+                        {
+                            self.save_config();
+                        }
+
                         ui.add_space(20.0);
 
                         // Show selection status
                         let from_text = match &self.copy_from {
-                            Some(id) => {
-                                let name = self.settings_files.iter()
-                                    .find(|f| f.character_id == *id)
-                                    .map(|f| match &f.character_name {
-                                        CharacterNameStatus::Found(n) => n.clone(),
-                                        _ => id.clone(),
-                                    })
-                                    .unwrap_or_else(|| id.clone());
-                                format!("From: {}", name)
-                            }
+                            Some(path) => format!("From: {}", self.path_display_name(path)),
                             None => "From: (none selected)".to_string(),
                         };
                         
@@ -577,6 +6002,16 @@ impl eframe::App for EveSettingsApp {
                         ui.label(&from_text);
                         ui.add_space(15.0);
                         ui.label(&to_text);
+
+                        let summary = self.selection_summary();
+                        if ui.add_enabled(summary.is_some(), egui::Button::new("📋 Copy summary"))
+                            .on_hover_text("Put \"Copying from X to: Y, Z\" on the clipboard")
+                            .clicked()
+                        {
+                            if let Some(summary) = summary {
+                                ui.ctx().copy_text(summary);
+                            }
+                        }
                     });
                     
                     // Help text when button is disabled
@@ -588,7 +6023,7 @@ impl eframe::App for EveSettingsApp {
                     }
                 });
 
-            } else if self.scan_complete && self.error_message.is_none() {
+            } else if self.scan_complete && self.scan_job.is_none() && self.error_message.is_none() {
                 ui.label(egui::RichText::new("No character settings files found in the specified path.")
                     .color(egui::Color32::GRAY)
                     .italics());
@@ -597,20 +6032,329 @@ impl eframe::App for EveSettingsApp {
             // Footer - centered (matching ToonTab style)
             ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
                 ui.add_space(5.0);
-                ui.label(egui::RichText::new("Version 1.0.0 - ChrisRid 2025")
+                ui.label(egui::RichText::new(format!("Version {} - ChrisRid 2025", APP_VERSION))
                     .color(egui::Color32::GRAY)
                     .small());
+                let requests = self.esi_requests.load(Ordering::Relaxed);
+                ui.label(egui::RichText::new(format!("{} ESI request(s) this session", requests))
+                    .color(egui::Color32::GRAY)
+                    .small())
+                    .on_hover_text("Names, corporations, alliances, portraits and server status; cached names don't count");
             });
         });
     }
 }
 
+const APP_VERSION: &str = "1.0.0";
+
+// Corporations named in the summary line before the rest become "+N more"
+const CORP_SUMMARY_LIMIT: usize = 5;
+
+const DEFAULT_WINDOW_SIZE: [f32; 2] = [1220.0, 600.0];
+const MIN_WINDOW_SIZE: [f32; 2] = [1220.0, 400.0];
+
+const CLI_USAGE: &str = "Usage:
+  toonsettings --copy-from <id> --copy-to <id,id,...> [--path <dir>] [--include-account] [--keep-mtime] [--verify] [--skip-identical] [--clear-read-only]
+  toonsettings --scan [--json] [--path <dir>]
+Run without arguments to start the GUI.";
+
+// Options for running headless from the command line
+#[derive(Debug, Default)]
+struct CliArgs {
+    path: Option<String>,
+    copy_from: Option<String>,
+    copy_to: Vec<String>,
+    copy_options: CopyOptions,
+    scan: bool,
+    json: bool,
+}
+
+fn parse_cli_args(args: &[String]) -> Result<CliArgs, String> {
+    let mut parsed = CliArgs::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--path" => parsed.path = Some(value()?),
+            "--copy-from" => parsed.copy_from = Some(value()?),
+            "--copy-to" => {
+                parsed.copy_to.extend(value()?
+                    .split(',')
+                    .map(|id| id.trim().to_string())
+                    .filter(|id| !id.is_empty()));
+            }
+            "--include-account" => parsed.copy_options.include_account_settings = true,
+            "--keep-mtime" => parsed.copy_options.preserve_modified = true,
+            "--verify" => parsed.copy_options.verify_after_copy = true,
+            "--skip-identical" => parsed.copy_options.skip_identical = true,
+            "--clear-read-only" => parsed.copy_options.clear_read_only = true,
+            "--scan" => parsed.scan = true,
+            "--json" => parsed.json = true,
+            other => return Err(format!("Unknown argument: {}", other)),
+        }
+    }
+    Ok(parsed)
+}
+
+// One settings file in `--scan --json` output
+#[derive(Debug, Serialize)]
+struct ScanEntry {
+    filename: String,
+    path: String,
+    character_id: String,
+    name: Option<String>,
+    // Why the name couldn't be resolved, when it wasn't
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name_error: Option<String>,
+    profile: String,
+    // Seconds since the Unix epoch
+    modified: Option<u64>,
+}
+
+// Resolve names the same way a GUI scan does, but wait for every lookup to finish
+fn resolve_names_blocking(character_ids: Vec<String>, settings: LookupSettings) -> HashMap<String, CharacterNameStatus> {
+    let mut name_cache = load_name_cache();
+    let mut not_found_cache = load_not_found_cache();
+    let (sender, receiver) = channel();
+    let token = LookupToken {
+        cancel: Arc::new(AtomicBool::new(false)),
+        generation: 0,
+        current_generation: Arc::new(AtomicU64::new(0)),
+        requests: Arc::new(AtomicU64::new(0)),
+    };
+
+    match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => {
+            runtime.block_on(run_api_lookups(
+                character_ids, name_cache.clone(), recent_not_found(&not_found_cache),
+                KnownOrganisations::default(), settings, token, sender,
+            ));
+        }
+        Err(e) => {
+            return character_ids.into_iter()
+                .map(|id| (id, CharacterNameStatus::Error(format!("Runtime error: {}", e))))
+                .collect();
+        }
+    }
+
+    let (names, cache_dirty, not_found_dirty) =
+        collect_lookup_results(receiver.try_iter(), &mut name_cache, &mut not_found_cache);
+    if cache_dirty {
+        let _ = save_name_cache(&name_cache);
+    }
+    if not_found_dirty {
+        let _ = save_not_found_cache(&not_found_cache);
+    }
+    names
+}
+
+// Later messages for an ID supersede earlier ones, as in the GUI. Returns the
+// names, and whether the name and not-found caches changed.
+fn collect_lookup_results(
+    messages: impl IntoIterator<Item = ApiMessage>,
+    name_cache: &mut HashMap<String, CachedName>,
+    not_found_cache: &mut HashMap<String, u64>,
+) -> (HashMap<String, CharacterNameStatus>, bool, bool) {
+    let mut names = HashMap::new();
+    let mut cache_dirty = false;
+    let mut not_found_dirty = false;
+    for msg in messages {
+        if let ApiMessage::Result { character_id, name, details } = msg {
+            let (name_changed, not_found_changed) =
+                merge_lookup_result(name_cache, not_found_cache, &character_id, &name, &details);
+            cache_dirty |= name_changed;
+            not_found_dirty |= not_found_changed;
+            names.insert(character_id, name);
+        }
+    }
+    (names, cache_dirty, not_found_dirty)
+}
+
+// What `--scan` prints for each file; names that couldn't be resolved say why
+fn scan_entries(files: &[SettingsFile], names: &HashMap<String, CharacterNameStatus>) -> Vec<ScanEntry> {
+    files.iter().map(|f| {
+        let (name, name_error) = match names.get(&f.character_id) {
+            Some(CharacterNameStatus::Found(name)) => (Some(name.clone()), None),
+            Some(CharacterNameStatus::Error(e)) => (None, Some(e.clone())),
+            Some(CharacterNameStatus::Cancelled) => (None, Some("Cancelled".to_string())),
+            Some(CharacterNameStatus::Offline) => (None, Some("Offline".to_string())),
+            Some(CharacterNameStatus::Deleted) => (None, Some("Character not found (deleted?)".to_string())),
+            Some(CharacterNameStatus::Loading | CharacterNameStatus::Retrying) | None => (None, Some("Not resolved".to_string())),
+        };
+        ScanEntry {
+            filename: f.filename.clone(),
+            path: f.path.display().to_string(),
+            character_id: f.character_id.clone(),
+            name,
+            name_error,
+            profile: f.profile.clone(),
+            modified: f.modified
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+        }
+    }).collect()
+}
+
+// Characters in the names file are named from it and never looked up
+fn run_cli_scan(mut files: Vec<SettingsFile>, json: bool, config: &AppConfig) -> i32 {
+    files.sort_by(|a, b| compare_character_ids(&a.character_id, &b.character_id));
+    let roster = match config.names_file.trim() {
+        "" => HashMap::new(),
+        path => load_names_file(Path::new(path)).unwrap_or_else(|e| {
+            eprintln!("Warning: {}", e);
+            HashMap::new()
+        }),
+    };
+    let ids: Vec<String> = files.iter()
+        .map(|f| f.character_id.clone())
+        .filter(|id| !roster.contains_key(id))
+        .collect();
+    let mut names = resolve_names_blocking(ids, config.lookup_settings());
+    names.extend(roster.into_iter().map(|(id, name)| (id, CharacterNameStatus::Found(name))));
+
+    let entries = scan_entries(&files, &names);
+
+    if json {
+        match serde_json::to_string_pretty(&entries) {
+            Ok(output) => println!("{}", output),
+            Err(e) => {
+                eprintln!("Failed to serialize scan results: {}", e);
+                return 1;
+            }
+        }
+    } else {
+        for entry in &entries {
+            let name = entry.name.as_deref().unwrap_or("(unresolved)");
+            println!("{}\t{}\t{}\t{}", entry.character_id, name, entry.profile, entry.filename);
+        }
+    }
+    0
+}
+
+// Headless entry point. Returns the process exit code.
+fn run_cli(args: &[String]) -> i32 {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", CLI_USAGE);
+        return 0;
+    }
+
+    let cli = match parse_cli_args(args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{}\n{}", e, CLI_USAGE);
+            return 2;
+        }
+    };
+    let source_id = match (&cli.copy_from, cli.copy_to.is_empty()) {
+        (Some(source_id), false) => Some(source_id.clone()),
+        (None, true) if cli.scan => None,
+        _ => {
+            eprintln!("Either --scan, or both --copy-from and --copy-to, are required\n{}", CLI_USAGE);
+            return 2;
+        }
+    };
+
+    // Same path and server the GUI would start with when none is given
+    let config = load_config();
+    let path = match cli.path.or_else(|| config.eve_path.clone()).or_else(get_eve_settings_path) {
+        Some(path) => path,
+        None => {
+            eprintln!("No home directory to find the default settings path in; pass --path");
+            return 2;
+        }
+    };
+    let files = match scan_for_settings_files(&path, config.scan_depth(), &AtomicBool::new(false)) {
+        Ok((files, unreadable)) => {
+            for warning in unreadable {
+                eprintln!("Warning: couldn't read {}", warning);
+            }
+            files
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+
+    let source_id = match source_id {
+        Some(source_id) => source_id,
+        None => return run_cli_scan(files, cli.json, &config),
+    };
+
+    if eve_client_running() {
+        eprintln!("Warning: EVE seems to be running; it may overwrite the copied settings when you log out");
+    }
+
+    // IDs name characters: the source is its usable file written last, the one EVE
+    // reads next, and every file of each destination is written
+    let sources: Vec<&SettingsFile> = files.iter().filter(|f| f.character_id == source_id).collect();
+    let usable: Vec<&SettingsFile> = sources.iter().copied().filter(|f| !f.invalid).collect();
+    let source = match usable.iter().max_by_key(|f| f.modified) {
+        Some(f) => {
+            if usable.len() > 1 {
+                eprintln!("{} has settings in {} profiles; copying from {} ({})",
+                    source_id, usable.len(), f.profile, f.path.display());
+            }
+            f.path.clone()
+        }
+        None if !sources.is_empty() => {
+            eprintln!("Source file is empty or truncated");
+            return 1;
+        }
+        None => {
+            eprintln!("Source file not found");
+            return 1;
+        }
+    };
+    let mut dest_paths = Vec::new();
+    let mut missing = 0;
+    for id in &cli.copy_to {
+        let before = dest_paths.len();
+        dest_paths.extend(files.iter()
+            .filter(|f| f.character_id == *id && f.path != source)
+            .map(|f| f.path.clone()));
+        if dest_paths.len() == before {
+            eprintln!("Destination file not found: {}", id);
+            missing += 1;
+        }
+    }
+    if dest_paths.is_empty() {
+        return 1;
+    }
+
+    match copy_settings_files(&files, &source, &dest_paths, cli.copy_options) {
+        Ok(outcome) => {
+            let _ = write_operation_log(&copy_log_entry(&source_id, &outcome));
+            println!("{}", outcome.summary());
+            if outcome.is_success() && missing == 0 { 0 } else { 1 }
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
 fn main() -> eframe::Result<()> {
+    // Any arguments mean a headless run; the GUI only starts without them
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(run_cli(&args));
+    }
+
+    // Restore the window where the user last left it, never below the minimum size
+    let config = load_config();
+    let size = config.window_size.unwrap_or(DEFAULT_WINDOW_SIZE);
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size([size[0].max(MIN_WINDOW_SIZE[0]), size[1].max(MIN_WINDOW_SIZE[1])])
+        .with_title("ToonSettings")
+        .with_min_inner_size(MIN_WINDOW_SIZE);
+    if let Some(position) = config.window_position {
+        viewport = viewport.with_position(position);
+    }
+
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([720.0, 600.0])
-            .with_title("ToonSettings")
-            .with_min_inner_size([720.0, 400.0]),
+        viewport,
         ..Default::default()
     };
     
@@ -620,3 +6364,201 @@ fn main() -> eframe::Result<()> {
         Box::new(|_cc| Ok(Box::new(EveSettingsApp::default()))),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An empty folder of its own for each test, as tests run in parallel
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("toonsettings_test_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn settings_file(profile: &str, character_id: &str) -> SettingsFile {
+        let filename = format!("core_char_{}.dat", character_id);
+        SettingsFile {
+            path: PathBuf::from("/eve").join(profile).join(&filename),
+            filename,
+            character_id: character_id.to_string(),
+            install: PathBuf::from("/eve"),
+            profile: profile.to_string(),
+            modified: None,
+            size: Some(1024),
+            invalid: false,
+            character_name: CharacterNameStatus::Loading,
+            details: CharacterDetails::default(),
+        }
+    }
+
+    fn lookup_token(generation: &Arc<AtomicU64>) -> LookupToken {
+        LookupToken {
+            cancel: Arc::new(AtomicBool::new(false)),
+            generation: generation.load(Ordering::Relaxed),
+            current_generation: generation.clone(),
+            requests: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    #[test]
+    fn no_home_directory_leaves_path_empty_with_guidance() {
+        assert_eq!(eve_settings_path_from(None, None), None);
+        let (eve_path, notice) = startup_path(None, eve_settings_path_from(None, None));
+        assert!(eve_path.is_empty());
+        assert_eq!(notice.as_deref(), Some(NO_HOME_NOTICE));
+    }
+
+    #[test]
+    fn wine_prefix_is_used_without_a_home_directory() {
+        let prefix = test_dir("wine_prefix");
+        let eve = prefix.join("drive_c/users/steamuser/AppData/Local/CCP/EVE");
+        fs::create_dir_all(&eve).unwrap();
+        assert_eq!(eve_settings_path_from(None, Some(&prefix)), Some(eve.to_string_lossy().to_string()));
+        let _ = fs::remove_dir_all(&prefix);
+    }
+
+    #[test]
+    fn names_arriving_in_any_order_keep_selections() {
+        let names = [("111", "Zed"), ("222", "Amy"), ("333", "Mia")];
+        let mut app = EveSettingsApp {
+            config: AppConfig::default(),
+            // Already cached, so nothing is written to the real config folder
+            name_cache: names.iter()
+                .map(|(id, name)| (id.to_string(), CachedName {
+                    name: name.to_string(),
+                    details: CharacterDetails::default(),
+                    cached_at: 0,
+                    user_provided: false,
+                }))
+                .collect(),
+            not_found_cache: HashMap::new(),
+            ..Default::default()
+        };
+        app.settings_files = vec![
+            settings_file("settings_Default", "111"),
+            settings_file("settings_Default", "222"),
+            settings_file("settings_Default", "333"),
+            settings_file("settings_Deep", "333"),
+        ];
+        app.sort_key = SortKey::CharacterName;
+        app.sort_files();
+
+        let source = app.settings_files[0].path.clone();
+        let destinations: HashSet<PathBuf> = app.settings_files[2..].iter().map(|f| f.path.clone()).collect();
+        app.copy_from = Some(source.clone());
+        app.copy_to = destinations.clone();
+
+        let (sender, receiver) = channel();
+        app.api_receiver = Some(receiver);
+        let ctx = egui::Context::default();
+        for (id, name) in [names[2], names[0], names[1]] {
+            sender.send(ApiMessage::Result {
+                character_id: id.to_string(),
+                name: CharacterNameStatus::Found(name.to_string()),
+                details: CharacterDetails::default(),
+            }).unwrap();
+            app.process_api_messages(&ctx);
+        }
+
+        let order: Vec<&str> = app.settings_files.iter().map(|f| f.character_id.as_str()).collect();
+        assert_eq!(order, ["222", "333", "333", "111"]);
+        assert_eq!(app.copy_from, Some(source.clone()));
+        assert_eq!(app.copy_to, destinations);
+        assert_eq!(app.file_at(&source).map(|f| f.character_id.as_str()), Some("111"));
+        assert!(app.copy_to.iter().all(|path| app.file_at(path).is_some_and(|f| f.character_id != "111")));
+    }
+
+    #[test]
+    fn backup_names_follow_the_scanned_installs_not_the_path_box() {
+        let mut app = EveSettingsApp {
+            config: AppConfig { additional_paths: vec!["/other".to_string()], ..AppConfig::default() },
+            scanned_installs: vec![PathBuf::from("/eve"), PathBuf::from("/other")],
+            // Edited after the scan
+            eve_path: "/eve-typo".to_string(),
+            ..Default::default()
+        };
+        let file = Path::new("/eve/settings_Default/core_char_111.dat");
+        assert_eq!(app.backup_entry_name(file, Path::new("/eve")).as_deref(), Some("settings_Default/core_char_111.dat"));
+        let extra = Path::new("/other/settings_Default/core_char_222.dat");
+        assert_eq!(app.backup_entry_name(extra, Path::new("/other")).as_deref(),
+            Some("additional_1/settings_Default/core_char_222.dat"));
+
+        // An install the last scan didn't use is outside the backup
+        app.config.additional_paths.clear();
+        app.scanned_installs.truncate(1);
+        assert_eq!(app.backup_entry_name(extra, Path::new("/other")), None);
+    }
+
+    #[test]
+    fn failed_cli_lookups_become_name_errors() {
+        let files = vec![
+            settings_file("settings_Default", "111"),
+            settings_file("settings_Default", "222"),
+            settings_file("settings_Default", "333"),
+        ];
+        let result = |id: &str, name: CharacterNameStatus| ApiMessage::Result {
+            character_id: id.to_string(),
+            name,
+            details: CharacterDetails::default(),
+        };
+        let messages = vec![
+            result("111", CharacterNameStatus::Found("Pilot".to_string())),
+            result("222", CharacterNameStatus::Error("Request timed out".to_string())),
+            result("333", CharacterNameStatus::Deleted),
+        ];
+        let mut name_cache = HashMap::new();
+        let mut not_found_cache = HashMap::new();
+        let (names, cache_dirty, not_found_dirty) =
+            collect_lookup_results(messages, &mut name_cache, &mut not_found_cache);
+        assert!(cache_dirty && not_found_dirty);
+        assert_eq!(name_cache.keys().collect::<Vec<_>>(), ["111"]);
+        assert!(not_found_cache.contains_key("333"));
+
+        let entries = scan_entries(&files, &names);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].name.as_deref(), Some("Pilot"));
+        assert_eq!(entries[0].name_error, None);
+        assert_eq!(entries[1].name, None);
+        assert_eq!(entries[1].name_error.as_deref(), Some("Request timed out"));
+        assert_eq!(entries[2].name_error.as_deref(), Some("Character not found (deleted?)"));
+        let json = serde_json::to_string(&entries).unwrap();
+        assert!(json.contains(r#""name_error":"Request timed out""#), "{}", json);
+    }
+
+    #[test]
+    fn newer_scan_supersedes_older_lookups() {
+        let scan_generation = Arc::new(AtomicU64::new(0));
+        let first = lookup_token(&scan_generation);
+        let second = lookup_token(&scan_generation);
+        assert!(!first.is_superseded() && !second.is_cancelled());
+
+        // What scan_files does when a second scan starts
+        scan_generation.fetch_add(1, Ordering::Relaxed);
+        assert!(first.is_superseded());
+        assert!(first.is_cancelled());
+        assert!(second.is_superseded());
+        assert!(!lookup_token(&scan_generation).is_cancelled());
+
+        let cached_names = HashMap::from([("111".to_string(), CachedName {
+            name: "Cached Pilot".to_string(),
+            details: CharacterDetails::default(),
+            cached_at: 0,
+            user_provided: true,
+        })]);
+        let (sender, receiver) = channel();
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(run_api_lookups(
+            vec!["111".to_string(), "222".to_string()],
+            cached_names,
+            HashSet::new(),
+            KnownOrganisations::default(),
+            AppConfig::default().lookup_settings(),
+            first.clone(),
+            sender,
+        ));
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(first.requests.load(Ordering::Relaxed), 0);
+    }
+}